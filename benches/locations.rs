@@ -45,11 +45,71 @@ fn lookup_v6(bench: &mut Bencher) {
     });
 }
 
+fn classify(bench: &mut Bencher) {
+    let locations = locations();
+    let mut classifier = locations.classifier();
+    let addr: IpAddr = ADDR.parse().unwrap();
+    bench.iter(|| {
+        black_box(classifier.classify(black_box(addr)));
+    });
+}
+
+/// Many pseudo-random IPv4 addresses.
+fn many_addrs() -> Vec<IpAddr> {
+    let mut state: u32 = 0x2545_f491;
+    (0..10_000)
+        .map(|_| {
+            // xorshift32
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            IpAddr::V4(state.into())
+        })
+        .collect()
+}
+
+fn lookup_loop(bench: &mut Bencher) {
+    let locations = locations();
+    let addrs = many_addrs();
+    bench.iter(|| {
+        for &addr in &addrs {
+            black_box(locations.lookup(black_box(addr)));
+        }
+    });
+}
+
+fn lookup_many(bench: &mut Bencher) {
+    let locations = locations();
+    let addrs = many_addrs();
+    bench.iter(|| {
+        black_box(locations.lookup_many(black_box(&addrs)));
+    });
+}
+
+fn networks(bench: &mut Bencher) {
+    let locations = locations();
+    bench.iter(|| {
+        black_box(locations.networks().count());
+    });
+}
+
+fn summary(bench: &mut Bencher) {
+    let locations = locations();
+    bench.iter(|| {
+        black_box(locations.summary().count());
+    });
+}
+
 #[rustfmt::skip]
 benchmark_group!(locations_main,
     open,
     lookup,
     lookup_v4,
     lookup_v6,
+    classify,
+    lookup_loop,
+    lookup_many,
+    networks,
+    summary,
 );
 benchmark_main!(locations_main);
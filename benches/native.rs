@@ -1,6 +1,7 @@
 use bencher::benchmark_main;
 
 #[cfg(not(windows))]
+#[allow(clippy::incompatible_msrv)] // benches are not bound by the MSRV
 mod inner {
     use bencher::benchmark_group;
     use bencher::black_box;
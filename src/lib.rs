@@ -11,19 +11,45 @@ use ipnet::IpNet;
 use ipnet::Ipv4Net;
 use ipnet::Ipv6Net;
 use memmap2::Mmap;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::error::Error;
 use std::fmt;
 use std::fs::File;
+use std::hash::Hash;
+use std::hash::Hasher;
 use std::io;
+use std::io::Read;
+use std::io::Write;
 use std::net::IpAddr;
 use std::net::Ipv4Addr;
 use std::net::Ipv6Addr;
+use std::ops;
+use std::ops::Deref;
 use std::path::Path;
 use std::str;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 use yoke::Yoke;
+use zerocopy::AsBytes;
 use zerocopy::FromBytes;
 
 mod format;
+#[cfg(feature = "mmdb")]
+mod mmdb;
+mod writer;
+
+pub use self::writer::DatabaseBuilder;
+pub use self::writer::TestDatabaseBuilder;
+
+/// The database format version read by this crate.
+///
+/// Databases of other versions are rejected by [`Locations::open`] with
+/// [`OpenError::UnsupportedVersion`]. See also [`Locations::format_version`].
+pub const SUPPORTED_VERSION: u8 = format::VERSION;
 
 /// Error type for the [`Locations::open`] function.
 #[derive(Debug)]
@@ -37,9 +63,14 @@ pub enum OpenError {
     Open(io::Error),
     /// Error memory-mapping database file.
     Mmap(io::Error),
+    /// Error reading database, see [`Locations::from_reader`].
+    Read(io::Error),
     /// Invalid database file magic, likely not the correct format.
     InvalidMagic,
     /// Unsupported database version.
+    ///
+    /// Version 1 is the only version libloc has released. Version 0 is
+    /// libloc's "unset" marker, not an older layout, and is rejected as well.
     UnsupportedVersion(u8),
     /// Couldn't read database file header, database corrupted.
     CouldntReadHeader,
@@ -53,6 +84,20 @@ pub enum OpenError {
     InvalidCountryRange,
     /// Invalid database header field: `string_pool`, database corrupted.
     InvalidStringPoolRange,
+    /// A database section doesn't consist of whole records, database
+    /// corrupted.
+    ///
+    /// Only returned by [`Locations::open_checked`].
+    InvalidSectionLayout {
+        /// Name of the section, e.g. `network_nodes`.
+        section: &'static str,
+        /// Offset of the section in the database file.
+        offset: u32,
+        /// Length of the section in bytes.
+        length: u32,
+        /// Size of a single record of the section in bytes.
+        record_size: usize,
+    },
 }
 
 impl Error for OpenError {
@@ -61,6 +106,7 @@ impl Error for OpenError {
         match self {
             Open(e) => Some(e),
             Mmap(e) => Some(e),
+            Read(e) => Some(e),
             InvalidMagic
             | UnsupportedVersion(_)
             | CouldntReadHeader
@@ -68,7 +114,8 @@ impl Error for OpenError {
             | InvalidNetworkRange
             | InvalidNetworkNodeRange
             | InvalidCountryRange
-            | InvalidStringPoolRange => None,
+            | InvalidStringPoolRange
+            | InvalidSectionLayout { .. } => None,
         }
     }
 }
@@ -79,6 +126,7 @@ impl fmt::Display for OpenError {
         match self {
             Open(e) => write!(f, "error opening database file: {}", e),
             Mmap(e) => write!(f, "error memory-mapping database file: {}", e),
+            Read(e) => write!(f, "error reading database: {}", e),
             InvalidMagic => "invalid database file magic, likely not the correct format".fmt(f),
             UnsupportedVersion(ver) => write!(f, "unsupported database version {}", ver),
             CouldntReadHeader => "couldn't read database file header, database corrupted".fmt(f),
@@ -95,25 +143,499 @@ impl fmt::Display for OpenError {
             InvalidStringPoolRange => {
                 "invalid database header field: string_pool, database corrupted".fmt(f)
             }
+            InvalidSectionLayout {
+                section,
+                offset,
+                length,
+                record_size,
+            } => write!(
+                f,
+                "invalid database section {}: {} bytes at offset {} are not made of \
+                 {}-byte records, database corrupted",
+                section, length, offset, record_size,
+            ),
+        }
+    }
+}
+
+/// Error type for the `try_` lookup functions like [`Locations::try_lookup`].
+///
+/// These errors are only returned for corrupt databases that passed the
+/// checks of [`Locations::open`], e.g. ones with network nodes pointing at
+/// nonexistent networks.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum LookupError {
+    /// A record references an index past the end of its section.
+    InvalidIndex {
+        /// The kind of record that was referenced, e.g. `"network"`.
+        what: &'static str,
+        /// The referenced index.
+        index: u32,
+        /// The number of records in the section.
+        len: usize,
+    },
+    /// A string reference points at or past the end of the string pool.
+    InvalidStrRef(u32),
+    /// A string in the string pool is missing its null termination.
+    MissingNullTermination(u32),
+    /// A string is not valid UTF-8.
+    InvalidUtf8 {
+        /// The kind of string, e.g. `"AS name"`.
+        what: &'static str,
+        /// The underlying UTF-8 error.
+        error: str::Utf8Error,
+    },
+}
+
+impl Error for LookupError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        use self::LookupError::*;
+        match self {
+            InvalidUtf8 { error, .. } => Some(error),
+            InvalidIndex { .. } | InvalidStrRef(_) | MissingNullTermination(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for LookupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::LookupError::*;
+        match self {
+            InvalidIndex { what, index, len } => write!(
+                f,
+                "invalid {} index: {} > {}, database corrupted",
+                what, index, len,
+            ),
+            InvalidStrRef(offset) => {
+                write!(f, "invalid str_ref: {}, database corrupted", offset)
+            }
+            MissingNullTermination(offset) => write!(
+                f,
+                "missing null termination for str_ref: {}, database corrupted",
+                offset,
+            ),
+            InvalidUtf8 { what, error } => {
+                write!(
+                    f,
+                    "invalid UTF-8 in {}: {}, database corrupted",
+                    what, error
+                )
+            }
+        }
+    }
+}
+
+/// Error type for the [`Locations::verify_signature`] function.
+#[cfg(feature = "signatures")]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum SignatureError {
+    /// The database is not signed.
+    NoSignature,
+    /// The public key is not a PEM-encoded ECDSA P-256 public key.
+    BadKey,
+    /// No signature of the database matches the public key.
+    VerificationFailed,
+}
+
+#[cfg(feature = "signatures")]
+impl Error for SignatureError {}
+
+#[cfg(feature = "signatures")]
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::SignatureError::*;
+        match self {
+            NoSignature => "database is not signed".fmt(f),
+            BadKey => "invalid public key, expected a PEM-encoded ECDSA P-256 key".fmt(f),
+            VerificationFailed => "database signature verification failed".fmt(f),
+        }
+    }
+}
+
+/// Error type for the [`Locations::verify_trie_acyclic`] function.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum TrieError {
+    /// A network node has a child index outside of the network node table.
+    ChildOutOfRange {
+        /// Index of the offending network node.
+        node: u32,
+        /// The out-of-range child index.
+        child: u32,
+    },
+    /// A network node has a child that doesn't come after it in the network
+    /// node table.
+    ChildNotForward {
+        /// Index of the offending network node.
+        node: u32,
+        /// The child index pointing backwards.
+        child: u32,
+    },
+}
+
+impl Error for TrieError {}
+
+impl fmt::Display for TrieError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::TrieError::*;
+        match self {
+            ChildOutOfRange { node, child } => write!(
+                f,
+                "network node {} has out-of-range child {}, database corrupted",
+                node, child,
+            ),
+            ChildNotForward { node, child } => write!(
+                f,
+                "network node {} has child {} not following it, database corrupted",
+                node, child,
+            ),
+        }
+    }
+}
+
+/// Error type for the [`Locations::validate`] function.
+///
+/// Each variant carries the index of the first offending record and the
+/// [`LookupError`] that looking it up would have caused.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ValidationError {
+    /// A string referenced from the header is invalid.
+    InvalidHeader {
+        /// The header field, e.g. `"vendor"`.
+        field: &'static str,
+        /// The underlying error.
+        error: LookupError,
+    },
+    /// A network node references a nonexistent child or network.
+    InvalidNetworkNode {
+        /// Index of the offending network node.
+        node: u32,
+        /// The underlying error.
+        error: LookupError,
+    },
+    /// A network has a country code that isn't valid UTF-8.
+    InvalidNetwork {
+        /// Index of the offending network.
+        network: u32,
+        /// The underlying error.
+        error: LookupError,
+    },
+    /// An AS has an invalid name.
+    InvalidAs {
+        /// Index of the offending AS.
+        as_: u32,
+        /// The underlying error.
+        error: LookupError,
+    },
+    /// A country has an invalid code or name.
+    InvalidCountry {
+        /// Index of the offending country.
+        country: u32,
+        /// The underlying error.
+        error: LookupError,
+    },
+    /// A country code or continent code is not two ASCII uppercase letters.
+    ///
+    /// Only returned by [`Locations::validate_strict`].
+    InvalidCountryCode {
+        /// Index of the offending country.
+        country: u32,
+        /// The field, `"code"` or `"continent_code"`.
+        field: &'static str,
+        /// The invalid code.
+        code: [u8; 2],
+    },
+    /// A network's country code is neither `"XX"` nor two ASCII uppercase
+    /// letters.
+    ///
+    /// Only returned by [`Locations::validate_strict`].
+    InvalidNetworkCountryCode {
+        /// Index of the offending network.
+        network: u32,
+        /// The invalid code.
+        code: [u8; 2],
+    },
+}
+
+impl Error for ValidationError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        use self::ValidationError::*;
+        match self {
+            InvalidHeader { error, .. }
+            | InvalidNetworkNode { error, .. }
+            | InvalidNetwork { error, .. }
+            | InvalidAs { error, .. }
+            | InvalidCountry { error, .. } => Some(error),
+            InvalidCountryCode { .. } | InvalidNetworkCountryCode { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::ValidationError::*;
+        match self {
+            InvalidHeader { field, error } => write!(f, "header field {}: {}", field, error),
+            InvalidNetworkNode { node, error } => write!(f, "network node {}: {}", node, error),
+            InvalidNetwork { network, error } => write!(f, "network {}: {}", network, error),
+            InvalidAs { as_, error } => write!(f, "as {}: {}", as_, error),
+            InvalidCountry { country, error } => write!(f, "country {}: {}", country, error),
+            InvalidCountryCode {
+                country,
+                field,
+                code,
+            } => write!(
+                f,
+                "country {}: invalid {} {:?}",
+                country,
+                field,
+                String::from_utf8_lossy(code),
+            ),
+            InvalidNetworkCountryCode { network, code } => write!(
+                f,
+                "network {}: invalid country code {:?}",
+                network,
+                String::from_utf8_lossy(code),
+            ),
+        }
+    }
+}
+
+/// An IP address family.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AddrFamily {
+    /// IPv4.
+    V4,
+    /// IPv6.
+    V6,
+}
+
+/// What the bytes of a database are stored in.
+///
+/// Returned by the [`Locations::backing`] function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Backing {
+    /// A memory-mapped file, see the safety discussion of
+    /// [`Locations::open`].
+    MmapFile,
+    /// Memory owned by the database.
+    OwnedBytes,
+}
+
+/// The header of a database file.
+///
+/// Returned by the [`Locations::header_info`] function.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct HeaderInfo {
+    /// The format version of the database, see [`Locations::format_version`].
+    pub version: u8,
+    /// The creation time as seconds since the Unix epoch, see
+    /// [`Locations::created_at_raw`].
+    pub created_at: u64,
+    /// The vendor, or `None` if the string pool is corrupt. Invalid UTF-8 is
+    /// replaced.
+    pub vendor: Option<String>,
+    /// The description, or `None` if the string pool is corrupt. Invalid
+    /// UTF-8 is replaced.
+    pub description: Option<String>,
+    /// The license, or `None` if the string pool is corrupt. Invalid UTF-8
+    /// is replaced.
+    pub license: Option<String>,
+    /// The sections of the database, in header order: `as`, `networks`,
+    /// `network_nodes`, `countries` and `string_pool`.
+    pub sections: Vec<SectionInfo>,
+    /// The length of the first signature in bytes, 0 if there is none.
+    pub signature1_length: u16,
+    /// The length of the second signature in bytes, 0 if there is none.
+    pub signature2_length: u16,
+    /// The size of the database file in bytes.
+    pub file_size: usize,
+}
+
+/// The descriptive metadata of a database.
+///
+/// Returned by the [`Locations::metadata`] function.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Metadata {
+    /// The vendor, see [`Locations::vendor`]. Invalid UTF-8 is replaced.
+    pub vendor: String,
+    /// The description, see [`Locations::description`]. Invalid UTF-8 is
+    /// replaced.
+    pub description: String,
+    /// The license, see [`Locations::license`]. Invalid UTF-8 is replaced.
+    pub license: String,
+    /// The creation time, see [`Locations::created_at_system_time`].
+    pub created_at: SystemTime,
+}
+
+/// The number of entries in a database and its size.
+///
+/// Returned by the [`Locations::stats`] function.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Stats {
+    /// The number of ASes, see [`Locations::autonomous_systems`].
+    pub as_count: usize,
+    /// The number of networks, see [`Locations::network_count`].
+    pub network_count: usize,
+    /// The number of nodes in the network tree, see
+    /// [`Locations::network_node_count`].
+    pub network_node_count: usize,
+    /// The number of countries, see [`Locations::countries`].
+    pub country_count: usize,
+    /// The length of the string pool in bytes.
+    pub string_pool_len: usize,
+    /// The length of the memory mapping or buffer holding the database in
+    /// bytes.
+    pub data_len: usize,
+}
+
+/// The location of a section in a database file.
+///
+/// See [`HeaderInfo`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct SectionInfo {
+    /// The name of the section.
+    pub name: &'static str,
+    /// The offset of the section in the database file.
+    pub offset: u32,
+    /// The length of the section in bytes.
+    pub length: u32,
+}
+
+impl HeaderInfo {
+    /// Whether the database carries a signature.
+    pub fn is_signed(&self) -> bool {
+        self.signature1_length != 0 || self.signature2_length != 0
+    }
+}
+
+/// How to look up IPv4-mapped IPv6 addresses like `::ffff:192.0.2.1`.
+///
+/// Used by the [`Locations::lookup_with_family`] function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PreferFamily {
+    /// Look up addresses as given, see [`Locations::lookup`].
+    Native,
+    /// Look up IPv4-mapped IPv6 addresses as IPv4 addresses.
+    V4,
+}
+
+/// An IP address or prefix to look up.
+///
+/// Parses from either an address like `192.0.2.1` or a prefix in CIDR
+/// notation like `192.0.2.0/24`. A bare address is a host address, not a
+/// prefix. Used by the [`Locations::lookup_query`] function.
+///
+/// ```
+/// use libloc::Query;
+///
+/// assert_eq!("192.0.2.1".parse(), Ok(Query::Addr("192.0.2.1".parse().unwrap())));
+/// assert_eq!("192.0.2.0/24".parse(), Ok(Query::Net("192.0.2.0/24".parse().unwrap())));
+/// assert!("192.0.2.0/33".parse::<Query>().is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Query {
+    /// A host address, see [`Locations::lookup`].
+    Addr(IpAddr),
+    /// A prefix, see [`Locations::lookup_net`].
+    Net(IpNet),
+}
+
+impl str::FromStr for Query {
+    type Err = ipnet::AddrParseError;
+    fn from_str(s: &str) -> Result<Query, ipnet::AddrParseError> {
+        match s.parse() {
+            Ok(addr) => Ok(Query::Addr(addr)),
+            Err(_) => s.parse().map(Query::Net),
+        }
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Query::Addr(addr) => addr.fmt(f),
+            Query::Net(net) => net.fmt(f),
         }
     }
 }
 
+impl From<IpAddr> for Query {
+    fn from(addr: IpAddr) -> Query {
+        Query::Addr(addr)
+    }
+}
+
+impl From<IpNet> for Query {
+    fn from(net: IpNet) -> Query {
+        Query::Net(net)
+    }
+}
+
+/// ```
+/// use libloc::{Locations, Query};
+///
+/// let locations = Locations::open("example-location.db")?;
+/// let net: ipnetwork::IpNetwork = "2a07:1c44:5800::/48".parse().unwrap();
+/// let network = locations.lookup_query(&Query::from(net)).unwrap();
+/// assert_eq!(network.addrs().to_string(), "2a07:1c44:5800::/40");
+///
+/// # Ok::<(), libloc::OpenError>(())
+/// ```
+#[cfg(feature = "ipnetwork")]
+impl From<ipnetwork::IpNetwork> for Query {
+    fn from(net: ipnetwork::IpNetwork) -> Query {
+        Query::Net(from_ipnetwork(net))
+    }
+}
+
 /// Information on an [AS] (autonomous system).
 ///
 /// Returned by the [`Locations::as_`] function.
 ///
 /// [AS]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
-#[derive(Debug)]
+#[derive(PartialEq, Eq, Hash)]
 pub struct As<'a> {
     asn: u32,
-    name: &'a str,
+    name: PoolStr<'a>,
 }
 
 /// Information on an IP network.
 ///
 /// Returned by the [`Locations::lookup`] function.
-#[derive(Debug)]
+///
+/// Networks compare equal if their prefixes, ASNs, country codes and flags
+/// are equal.
+///
+/// ```
+/// use libloc::Locations;
+/// use std::collections::HashSet;
+///
+/// let locations = Locations::open("example-location.db")?;
+/// let addrs = ["2a07:1c44:5800::1", "2a07:1c44:5800::2", "2a07:1c44:58ff::1"];
+/// let networks: HashSet<_> = addrs
+///     .iter()
+///     .filter_map(|addr| locations.lookup(addr.parse().unwrap()))
+///     .collect();
+/// assert_eq!(networks.len(), 1);
+///
+/// let countries: HashSet<_> = networks
+///     .iter()
+///     .filter_map(|network| locations.country(network.country_code()))
+///     .collect();
+/// assert!(countries.contains(&locations.country("DE").unwrap()));
+///
+/// # Ok::<(), libloc::OpenError>(())
+/// ```
+#[derive(Debug, PartialEq, Eq, Hash)]
 pub struct Network<'a> {
     inner: NetworkInner<'a>,
     addrs: IpNet,
@@ -122,7 +644,7 @@ pub struct Network<'a> {
 /// Information on an IPv4 network.
 ///
 /// See [`Network`].
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash)]
 pub struct NetworkV4<'a> {
     inner: NetworkInner<'a>,
     addrs: Ipv4Net,
@@ -131,734 +653,5766 @@ pub struct NetworkV4<'a> {
 /// Information on an IPv6 network.
 ///
 /// See [`Network`].
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash)]
 pub struct NetworkV6<'a> {
     inner: NetworkInner<'a>,
     addrs: Ipv6Net,
 }
 
-#[derive(Debug)]
 struct NetworkInner<'a> {
-    // TODO: how to deal with XX? treat it as None?
-    country_code: &'a str,
-    // TODO: how to deal with AS0? treat it as None?
+    // `XX` if unknown, see `Network::country`.
+    country_code: &'a [u8; 2],
+    // 0 if unknown, see `Network::autonomous_system_number`.
     asn: u32,
     flags: u16,
+    updated_at: u64,
 }
 
+/// The flags of a network, a set of bits.
+///
+/// Returned by the [`Network::flags`] function. Flags unknown to this crate
+/// are preserved. Sets of flags can be combined with `|` and `&`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct NetworkFlags(u16);
+
 /// Information on a country.
 ///
 /// Returned by the [`Locations::country`] function.
-#[derive(Debug)]
+#[derive(PartialEq, Eq, Hash)]
 pub struct Country<'a> {
-    code: &'a str,
-    continent_code: &'a str,
-    name: &'a str,
+    code: &'a [u8; 2],
+    continent_code: &'a [u8; 2],
+    name: PoolStr<'a>,
 }
 
-impl<'a> As<'a> {
-    fn from(inner: &LocationsInner<'a>, as_: &'a format::As) -> As<'a> {
-        As {
-            asn: as_.id.get(),
-            name: inner.string(as_.name),
-        }
+/// Owned information on an [AS] (autonomous system).
+///
+/// Unlike [`As`], this doesn't borrow from the database. Returned by the
+/// [`As::to_owned`] function.
+///
+/// [AS]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
+#[derive(Clone, Debug, PartialEq)]
+pub struct AsOwned {
+    asn: u32,
+    name: String,
+}
+
+/// Owned information on an IP network.
+///
+/// Unlike [`Network`], this doesn't borrow from the database. Returned by the
+/// [`Network::to_owned`] function.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NetworkOwned {
+    addrs: IpNet,
+    country_code: String,
+    asn: u32,
+    flags: u16,
+}
+
+/// Owned information on a country.
+///
+/// Unlike [`Country`], this doesn't borrow from the database. Returned by the
+/// [`Country::to_owned`] function.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CountryOwned {
+    code: String,
+    continent_code: String,
+    name: String,
+}
+
+/// The networks and addresses held by an [AS] (autonomous system).
+///
+/// Returned by the [`Locations::lookup_as_footprint`] function.
+///
+/// [AS]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
+#[derive(Debug)]
+pub struct AsFootprint<'a> {
+    as_: As<'a>,
+    network_count: usize,
+    ipv4_address_count: u64,
+    ipv6_address_count: u128,
+}
+
+/// A network together with the address it was looked up for.
+///
+/// Returned by the [`Locations::lookup_with_query`] function.
+#[derive(Debug)]
+pub struct QueryMatch<'a> {
+    query: IpAddr,
+    network: Network<'a>,
+}
+
+/// A network together with how it was found in the network tree.
+///
+/// Returned by the [`Locations::lookup_detailed`] function.
+#[derive(Debug)]
+pub struct LookupDetails<'a> {
+    network: Network<'a>,
+    walked_bits: u8,
+    address_bits: u8,
+}
+
+/// A network together with its AS and country.
+///
+/// Returned by the [`Locations::describe`] function.
+#[derive(Debug)]
+pub struct Description<'a> {
+    network: Network<'a>,
+    as_: Option<As<'a>>,
+    country: Option<Country<'a>>,
+}
+
+#[cfg(feature = "time")]
+fn timestamp(secs: u64, what: &str) -> chrono::DateTime<chrono::offset::Utc> {
+    chrono::DateTime::from_timestamp(
+        secs.try_into().unwrap_or_else(|_| {
+            panic!("corrupt libloc db: invalid {}: {}", what, secs);
+        }),
+        0,
+    )
+    .unwrap_or_else(|| {
+        panic!("corrupt libloc db: invalid {}: {}", what, secs);
+    })
+}
+
+/// The number of addresses in a prefix with `host_bits` host bits,
+/// saturating at [`u128::MAX`].
+fn host_count(host_bits: u8) -> u128 {
+    1u128.checked_shl(host_bits.into()).unwrap_or(u128::MAX)
+}
+
+/// The IPv6 prefix of the given length whose bits are the lowest bits of
+/// `bits`.
+fn prefix_v6(bits: u128, len: u8) -> Ipv6Net {
+    let addr = bits.checked_shl(128 - u32::from(len)).unwrap_or(0);
+    Ipv6Net::new(addr.into(), len).unwrap()
+}
+
+/// The advice for memory-mapped databases, see [`Locations::open`].
+#[cfg(unix)]
+fn mmap_advice() -> memmap2::Advice {
+    use memmap2::Advice;
+    match std::env::var("LIBLOC_MMAP_ADVICE").as_deref() {
+        Ok("sequential") => Advice::Sequential,
+        Ok("normal") => Advice::Normal,
+        Ok("willneed") => Advice::WillNeed,
+        _ => Advice::Random,
     }
-    /// The [ASN] (number) of the [AS].
-    ///
-    /// [AS]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
-    /// [ASN]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
-    pub fn asn(&self) -> u32 {
-        self.asn
+}
+
+/// Lock the pages containing `bytes` into memory, see `mlock(2)`.
+#[cfg(unix)]
+fn mlock(bytes: &[u8]) -> io::Result<()> {
+    if bytes.is_empty() {
+        return Ok(());
     }
-    /// The human-readable name of the AS.
-    pub fn name(&self) -> &'a str {
-        self.name
+    // POSIX allows requiring page-aligned addresses.
+    let page_size = match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        -1 => return Err(io::Error::last_os_error()),
+        page_size => page_size as usize,
+    };
+    let start = bytes.as_ptr() as usize;
+    let aligned_start = start - start % page_size;
+    let len = start + bytes.len() - aligned_start;
+    if unsafe { libc::mlock(aligned_start as *const libc::c_void, len) } != 0 {
+        return Err(io::Error::last_os_error());
     }
+    Ok(())
 }
 
-impl<'a> NetworkInner<'a> {
-    fn from(_inner: &LocationsInner<'a>, network: &'a format::Network) -> NetworkInner<'a> {
-        NetworkInner {
-            country_code: str::from_utf8(&network.country_code).unwrap_or_else(|e| {
-                panic!(
-                    "corrupt libloc db: invalid UTF-8 in network country code: {}",
-                    e,
-                );
-            }),
-            asn: network.asn.get(),
-            flags: network.flags.get(),
+#[cold]
+#[inline(never)]
+fn corrupt(error: LookupError) -> ! {
+    panic!("libloc: {}", error);
+}
+
+/// The record at `index` of a database section.
+#[inline]
+fn record<'a, T>(section: &'a [T], what: &'static str, index: u32) -> Result<&'a T, LookupError> {
+    section
+        .get(index as usize)
+        .ok_or(LookupError::InvalidIndex {
+            what,
+            index,
+            len: section.len(),
+        })
+}
+
+/// Like [`prefix_v6`], but returns IPv4 prefixes for prefixes below
+/// `::ffff:0:0/96`, where IPv4 networks are stored.
+fn prefix(bits: u128, len: u8) -> IpNet {
+    unmap_prefix(prefix_v6(bits, len))
+}
+
+/// Convert IPv6 prefixes below `::ffff:0:0/96` to IPv4 prefixes.
+fn unmap_prefix(prefix: Ipv6Net) -> IpNet {
+    match prefix.network().to_ipv4_mapped() {
+        Some(addr) if prefix.prefix_len() >= 96 => {
+            Ipv4Net::new(addr, prefix.prefix_len() - 96).unwrap().into()
         }
+        _ => prefix.into(),
     }
 }
 
-impl<'a> Network<'a> {
-    /// The [ISO 3166-1 alpha-2] country code of the country associated with
-    /// this network.
-    ///
-    /// `"XX"` if unknown.
-    ///
-    /// [ISO 3166-1 alpha-2]: https://en.wikipedia.org/wiki/ISO_3166-1_alpha-2
-    ///
-    /// ```
-    /// use libloc::Locations;
-    ///
-    /// let locations = Locations::open("example-location.db")?;
-    /// let network: libloc::Network = locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap();
-    /// assert_eq!(network.country_code(), "DE");
-    ///
-    /// # Ok::<(), libloc::OpenError>(())
-    /// ```
-    pub fn country_code(&self) -> &'a str {
-        self.inner.country_code
+/// The reserved IPv4 ranges, excluded by [`Locations::coverage_ratio`].
+const RESERVED_V4: [(Ipv4Addr, u8); 14] = [
+    (Ipv4Addr::new(0, 0, 0, 0), 8),
+    (Ipv4Addr::new(10, 0, 0, 0), 8),
+    (Ipv4Addr::new(100, 64, 0, 0), 10),
+    (Ipv4Addr::new(127, 0, 0, 0), 8),
+    (Ipv4Addr::new(169, 254, 0, 0), 16),
+    (Ipv4Addr::new(172, 16, 0, 0), 12),
+    (Ipv4Addr::new(192, 0, 0, 0), 24),
+    (Ipv4Addr::new(192, 0, 2, 0), 24),
+    (Ipv4Addr::new(192, 168, 0, 0), 16),
+    (Ipv4Addr::new(198, 18, 0, 0), 15),
+    (Ipv4Addr::new(198, 51, 100, 0), 24),
+    (Ipv4Addr::new(203, 0, 113, 0), 24),
+    (Ipv4Addr::new(224, 0, 0, 0), 4),
+    (Ipv4Addr::new(240, 0, 0, 0), 4),
+];
+
+/// The globally routable IPv6 range, see [`Locations::coverage_ratio`].
+const GLOBAL_UNICAST_V6: (Ipv6Addr, u8) = (Ipv6Addr::new(0x2000, 0, 0, 0, 0, 0, 0, 0), 3);
+
+/// The reserved IPv6 ranges within [`GLOBAL_UNICAST_V6`], excluded by
+/// [`Locations::coverage_ratio`].
+const RESERVED_V6: [(Ipv6Addr, u8); 4] = [
+    (Ipv6Addr::new(0x2001, 0, 0, 0, 0, 0, 0, 0), 23),
+    (Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 32),
+    (Ipv6Addr::new(0x2002, 0, 0, 0, 0, 0, 0, 0), 16),
+    (Ipv6Addr::new(0x3fff, 0, 0, 0, 0, 0, 0, 0), 20),
+];
+
+/// The number of addresses two prefixes have in common.
+///
+/// The prefixes are given as their address bits and length, for addresses
+/// `width` bits wide.
+fn prefix_overlap((a, a_len): (u128, u8), (b, b_len): (u128, u8), width: u8) -> u128 {
+    let shift = u32::from(width - a_len.min(b_len));
+    if a.checked_shr(shift).unwrap_or(0) != b.checked_shr(shift).unwrap_or(0) {
+        return 0;
     }
-    /// The [ASN] of this network.
-    ///
-    /// 0 if unknown.
-    ///
-    /// ```
-    /// use libloc::Locations;
-    ///
-    /// let locations = Locations::open("example-location.db")?;
-    /// let network: libloc::Network = locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap();
-    /// assert_eq!(network.asn(), 204867);
-    ///
-    /// # Ok::<(), libloc::OpenError>(())
-    /// ```
-    ///
-    /// [ASN]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
-    pub fn asn(&self) -> u32 {
-        self.inner.asn
+    1u128
+        .checked_shl(u32::from(width - a_len.max(b_len)))
+        .unwrap_or(u128::MAX)
+}
+
+/// The [FNV-1a] hash of the given byte strings.
+///
+/// Each byte string is preceded by its length, so that moving bytes from
+/// one to the next changes the hash. Unlike the hashers of the standard
+/// library, the result is stable across platforms and Rust versions.
+///
+/// [FNV-1a]: https://en.wikipedia.org/wiki/Fowler%E2%80%93Noll%E2%80%93Vo_hash_function
+fn fnv1a(parts: &[&[u8]]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for part in parts {
+        for &b in (part.len() as u64).to_be_bytes().iter().chain(*part) {
+            hash ^= u64::from(b);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
     }
-    /// Whether the network hosts anonymous proxies.
-    ///
-    /// ```
-    /// use libloc::Locations;
-    ///
-    /// let locations = Locations::open("example-location.db")?;
-    /// let network: libloc::Network = locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap();
-    /// assert_eq!(network.is_anonymous_proxy(), false);
-    ///
-    /// # Ok::<(), libloc::OpenError>(())
-    /// ```
-    ///
-    /// [ASN]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
-    pub fn is_anonymous_proxy(&self) -> bool {
-        self.inner.flags & format::NETWORK_FLAG_ANONYMOUS_PROXY != 0
+    hash
+}
+
+/// Format a Unix timestamp as `YYYYMMDDhhmmss` in UTC.
+fn compact_timestamp(secs: u64) -> String {
+    // See http://howardhinnant.github.io/date_algorithms.html#civil_from_days.
+    let (days, secs) = (secs / 86400, secs % 86400);
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!(
+        "{:04}{:02}{:02}{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+    )
+}
+
+/// Write a CSV field, quoting it if necessary.
+fn write_csv_field<W: Write>(w: &mut W, s: &str) -> io::Result<()> {
+    if !s.contains(&[',', '"', '\r', '\n'][..]) {
+        return w.write_all(s.as_bytes());
     }
-    /// Whether the network is a satellite provider.
+    write!(w, "\"{}\"", s.replace('"', "\"\""))
+}
+
+/// Write a JSON string literal.
+fn write_json_string<W: Write>(w: &mut W, s: &str) -> io::Result<()> {
+    w.write_all(b"\"")?;
+    let mut rest = s;
+    while let Some(i) = rest.find(|c: char| c == '"' || c == '\\' || c < ' ') {
+        w.write_all(&rest.as_bytes()[..i])?;
+        match rest.as_bytes()[i] {
+            b'"' => w.write_all(b"\\\"")?,
+            b'\\' => w.write_all(b"\\\\")?,
+            b'\n' => w.write_all(b"\\n")?,
+            c => write!(w, "\\u{:04x}", c)?,
+        }
+        rest = &rest[i + 1..];
+    }
+    w.write_all(rest.as_bytes())?;
+    w.write_all(b"\"")
+}
+
+fn from_utf8<'a>(bytes: &'a [u8], what: &'static str) -> &'a str {
+    try_from_utf8(bytes, what).unwrap_or_else(|e| corrupt(e))
+}
+
+fn try_from_utf8<'a>(bytes: &'a [u8], what: &'static str) -> Result<&'a str, LookupError> {
+    str::from_utf8(bytes).map_err(|error| LookupError::InvalidUtf8 { what, error })
+}
+
+/// A string of the string pool, keeping invalid references around so that
+/// strict accessors can panic on them while lossy ones don't.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum PoolStr<'a> {
+    Valid(&'a [u8]),
+    /// The reference points at or past the end of the string pool.
+    OutOfBounds(u32),
+    /// The string runs to the end of the string pool without a null
+    /// termination.
+    Unterminated(u32, &'a [u8]),
+}
+
+impl<'a> PoolStr<'a> {
+    fn result(self) -> Result<&'a [u8], LookupError> {
+        match self {
+            PoolStr::Valid(bytes) => Ok(bytes),
+            PoolStr::OutOfBounds(offset) => Err(LookupError::InvalidStrRef(offset)),
+            PoolStr::Unterminated(offset, _) => Err(LookupError::MissingNullTermination(offset)),
+        }
+    }
+    fn bytes(self) -> &'a [u8] {
+        self.result().unwrap_or_else(|e| corrupt(e))
+    }
+    fn bytes_lossy(self) -> &'a [u8] {
+        match self {
+            PoolStr::Valid(bytes) | PoolStr::Unterminated(_, bytes) => bytes,
+            PoolStr::OutOfBounds(_) => &[],
+        }
+    }
+}
+
+impl<'a> fmt::Debug for As<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("As")
+            .field("asn", &self.asn)
+            .field("name", &self.name_lossy())
+            .finish()
+    }
+}
+
+impl<'a> fmt::Debug for NetworkInner<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NetworkInner")
+            .field("country_code", &String::from_utf8_lossy(self.country_code))
+            .field("asn", &self.asn)
+            .field("flags", &self.flags)
+            .field("updated_at", &self.updated_at)
+            .finish()
+    }
+}
+
+impl<'a> fmt::Debug for Country<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Country")
+            .field("code", &self.code_lossy())
+            .field("continent_code", &self.continent_code_lossy())
+            .field("name", &self.name_lossy())
+            .finish()
+    }
+}
+
+// `updated_at` is the same for all networks of a database, so it is left
+// out to allow comparing networks of different databases.
+impl<'a> PartialEq for NetworkInner<'a> {
+    fn eq(&self, other: &NetworkInner<'a>) -> bool {
+        self.country_code == other.country_code
+            && self.asn == other.asn
+            && self.flags == other.flags
+    }
+}
+
+impl<'a> Eq for NetworkInner<'a> {}
+
+impl<'a> Hash for NetworkInner<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.country_code.hash(state);
+        self.asn.hash(state);
+        self.flags.hash(state);
+    }
+}
+
+impl<'a> NetworkInner<'a> {
+    /// Format the network with the given addresses for humans, see
+    /// `Network`'s `Display` implementation.
+    fn display(&self, addrs: &dyn fmt::Display, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", addrs)?;
+        if self.asn != 0 {
+            write!(f, " AS{}", self.asn)?;
+        }
+        if self.country_code != b"XX" {
+            write!(f, " {}", String::from_utf8_lossy(self.country_code))?;
+        }
+        let names = self.flag_names();
+        let unknown = self.unknown_flags();
+        if !names.is_empty() || unknown != 0 {
+            write!(f, " [{}", names.join(", "))?;
+            if unknown != 0 {
+                let sep = if names.is_empty() { "" } else { ", " };
+                write!(f, "{}{:#06x}", sep, unknown)?;
+            }
+            write!(f, "]")?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats the network for humans, like `2a07:1c44:5800::/40 AS204867 DE
+/// [anycast]`.
+///
+/// The ASN is left out if it is 0, the country code if it is `XX`. Flags
+/// unknown to this crate are shown as a hexadecimal number.
+///
+/// ```
+/// use libloc::{Locations, NetworkFlags, TestDatabaseBuilder};
+///
+/// let locations = Locations::open("example-location.db")?;
+/// let network = locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap();
+/// assert_eq!(network.to_string(), "2a07:1c44:5800::/40 AS204867 DE [anycast]");
+///
+/// let flags = NetworkFlags::from_bits(0x0019);
+/// let locations = TestDatabaseBuilder::new()
+///     .add_network("192.0.2.0/24", 0, "XX")
+///     .add_network_with_flags("198.51.100.0/24", 64496, "DE", flags)
+///     .build();
+/// let network = locations.lookup_v4("192.0.2.1".parse().unwrap()).unwrap();
+/// assert_eq!(network.to_string(), "192.0.2.0/24");
+/// let network = locations.lookup_v4("198.51.100.1".parse().unwrap()).unwrap();
+/// assert_eq!(
+///     network.to_string(),
+///     "198.51.100.0/24 AS64496 DE [anonymous_proxy, drop, 0x0010]",
+/// );
+///
+/// # Ok::<(), libloc::OpenError>(())
+/// ```
+impl<'a> fmt::Display for Network<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.display(&self.addrs, f)
+    }
+}
+
+/// See [`Network`]'s `Display` implementation.
+impl<'a> fmt::Display for NetworkV4<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.display(&self.addrs, f)
+    }
+}
+
+/// See [`Network`]'s `Display` implementation.
+impl<'a> fmt::Display for NetworkV6<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.display(&self.addrs, f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'a> serde::Serialize for As<'a> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut s = serializer.serialize_struct("As", 2)?;
+        s.serialize_field("asn", &self.asn)?;
+        s.serialize_field("name", &self.name_lossy())?;
+        s.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'a> serde::Serialize for Country<'a> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut s = serializer.serialize_struct("Country", 3)?;
+        s.serialize_field("code", &self.code_lossy())?;
+        s.serialize_field("continent_code", &self.continent_code_lossy())?;
+        s.serialize_field("name", &self.name_lossy())?;
+        s.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'a> NetworkInner<'a> {
+    /// Serialize the network with the given addresses, with its flags as
+    /// named booleans.
+    fn serialize<S: serde::Serializer>(
+        &self,
+        name: &'static str,
+        addrs: &dyn fmt::Display,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let flags = self.flags();
+        let mut s = serializer.serialize_struct(name, 7)?;
+        s.serialize_field("addrs", &addrs.to_string())?;
+        s.serialize_field("country_code", &String::from_utf8_lossy(self.country_code))?;
+        s.serialize_field("asn", &self.asn)?;
+        s.serialize_field(
+            "is_anonymous_proxy",
+            &flags.contains(NetworkFlags::ANONYMOUS_PROXY),
+        )?;
+        s.serialize_field(
+            "is_satellite_provider",
+            &flags.contains(NetworkFlags::SATELLITE_PROVIDER),
+        )?;
+        s.serialize_field("is_anycast", &flags.contains(NetworkFlags::ANYCAST))?;
+        s.serialize_field("is_drop", &flags.contains(NetworkFlags::DROP))?;
+        s.end()
+    }
+}
+
+/// Serializes the network as a struct with the fields `addrs` (as a string),
+/// `country_code`, `asn`, `is_anonymous_proxy`, `is_satellite_provider`,
+/// `is_anycast` and `is_drop`.
+///
+/// ```
+/// use libloc::Locations;
+///
+/// let locations = Locations::open("example-location.db")?;
+/// let network = locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap();
+/// assert_eq!(
+///     serde_json::to_string(&network).unwrap(),
+///     "{\"addrs\":\"2a07:1c44:5800::/40\",\"country_code\":\"DE\",\"asn\":204867,\
+///      \"is_anonymous_proxy\":false,\"is_satellite_provider\":false,\
+///      \"is_anycast\":true,\"is_drop\":false}",
+/// );
+/// let as_ = locations.as_(network.asn()).unwrap();
+/// assert_eq!(
+///     serde_json::to_string(&as_).unwrap(),
+///     "{\"asn\":204867,\"name\":\"Lightning Wire Labs GmbH\"}",
+/// );
+/// let country = locations.country(network.country_code()).unwrap();
+/// assert_eq!(
+///     serde_json::to_string(&country).unwrap(),
+///     "{\"code\":\"DE\",\"continent_code\":\"EU\",\"name\":\"Germany\"}",
+/// );
+///
+/// # Ok::<(), libloc::OpenError>(())
+/// ```
+#[cfg(feature = "serde")]
+impl<'a> serde::Serialize for Network<'a> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.inner.serialize("Network", &self.addrs, serializer)
+    }
+}
+
+/// See [`Network`]'s `Serialize` implementation.
+#[cfg(feature = "serde")]
+impl<'a> serde::Serialize for NetworkV4<'a> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.inner.serialize("NetworkV4", &self.addrs, serializer)
+    }
+}
+
+/// See [`Network`]'s `Serialize` implementation.
+#[cfg(feature = "serde")]
+impl<'a> serde::Serialize for NetworkV6<'a> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.inner.serialize("NetworkV6", &self.addrs, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Metadata {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let created_at = self
+            .created_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let mut s = serializer.serialize_struct("Metadata", 4)?;
+        s.serialize_field("vendor", &self.vendor)?;
+        s.serialize_field("description", &self.description)?;
+        s.serialize_field("license", &self.license)?;
+        s.serialize_field("created_at", &created_at)?;
+        s.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'a> serde::Serialize for Description<'a> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut s = serializer.serialize_struct("Description", 3)?;
+        s.serialize_field("network", &self.network)?;
+        s.serialize_field("as", &self.as_)?;
+        s.serialize_field("country", &self.country)?;
+        s.end()
+    }
+}
+
+impl<'a> As<'a> {
+    fn from(inner: &LocationsInner<'a>, as_: &'a format::As) -> As<'a> {
+        As {
+            asn: as_.id.get(),
+            name: inner.pool_str(as_.name),
+        }
+    }
+    /// The [ASN] (number) of the [AS].
     ///
-    /// ```
-    /// use libloc::Locations;
+    /// [AS]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
+    /// [ASN]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
+    pub fn asn(&self) -> u32 {
+        self.asn
+    }
+    /// The human-readable name of the AS.
+    pub fn name(&self) -> &'a str {
+        from_utf8(self.name.bytes(), "AS name")
+    }
+    /// The human-readable name of the AS, `None` if it has no name.
     ///
-    /// let locations = Locations::open("example-location.db")?;
-    /// let network: libloc::Network = locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap();
-    /// assert_eq!(network.is_satellite_provider(), false);
+    /// The string pool of libloc databases starts with an empty string, so
+    /// an AS without a name points at offset 0. Both that and any other empty
+    /// name are reported as `None`.
     ///
-    /// # Ok::<(), libloc::OpenError>(())
     /// ```
+    /// use libloc::TestDatabaseBuilder;
     ///
-    /// [ASN]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
-    pub fn is_satellite_provider(&self) -> bool {
-        self.inner.flags & format::NETWORK_FLAG_SATTELITE_PROVIDER != 0
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_as(64496, "Example AS")
+    ///     .add_as(64497, "")
+    ///     .build();
+    /// assert_eq!(locations.as_(64496).unwrap().name_opt(), Some("Example AS"));
+    /// assert_eq!(locations.as_(64497).unwrap().name_opt(), None);
+    /// ```
+    pub fn name_opt(&self) -> Option<&'a str> {
+        if self.name.bytes().is_empty() {
+            return None;
+        }
+        Some(self.name())
     }
-    /// Whether the network consists of [anycast] addresses.
+    /// The human-readable name of the AS, replacing invalid UTF-8.
+    ///
+    /// Unlike [`As::name`], this never panics on invalid UTF-8 in a corrupt
+    /// database, see [`String::from_utf8_lossy`]. Names referencing strings
+    /// outside of the string pool are empty, names missing their null
+    /// termination end at the end of the string pool.
     ///
     /// ```
     /// use libloc::Locations;
     ///
     /// let locations = Locations::open("example-location.db")?;
-    /// let network: libloc::Network = locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap();
-    /// assert_eq!(network.is_anycast(), true);
+    /// assert_eq!(locations.as_(204867).unwrap().name_lossy(), "Lightning Wire Labs GmbH");
+    ///
+    /// let mut data = std::fs::read("example-location.db").unwrap();
+    /// // Point the AS at a string past the end of the string pool.
+    /// let as_ = u32::from_be_bytes(data[28..32].try_into().unwrap()) as usize;
+    /// data[as_ + 4..as_ + 8].copy_from_slice(&u32::MAX.to_be_bytes());
+    /// let locations = Locations::from_bytes(data)?;
+    /// let as_ = locations.as_(204867).unwrap();
+    /// assert_eq!(as_.name_lossy(), "");
+    /// // The strict accessor still refuses the corrupt name.
+    /// assert!(std::panic::catch_unwind(|| as_.name()).is_err());
     ///
     /// # Ok::<(), libloc::OpenError>(())
     /// ```
-    ///
-    /// [anycast]: https://en.wikipedia.org/wiki/Anycast
-    pub fn is_anycast(&self) -> bool {
-        self.inner.flags & format::NETWORK_FLAG_ANYCAST != 0
-    }
-    #[allow(missing_docs)]
-    pub fn is_drop(&self) -> bool {
-        self.inner.flags & format::NETWORK_FLAG_DROP != 0
+    pub fn name_lossy(&self) -> Cow<'a, str> {
+        String::from_utf8_lossy(self.name.bytes_lossy())
     }
-    /// All the addresses belonging to this particular network.
+    /// Copy the AS information out of the database.
+    ///
+    /// The result can outlive the [`Locations`] it was looked up in. Invalid
+    /// UTF-8 in the name is replaced, see [`String::from_utf8_lossy`].
     ///
     /// ```
     /// use libloc::Locations;
     ///
     /// let locations = Locations::open("example-location.db")?;
-    /// let network: libloc::Network = locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap();
-    /// assert_eq!(network.addrs().to_string(), "2a07:1c44:5800::/40");
+    /// let as_ = locations.as_(204867).unwrap().to_owned();
+    /// drop(locations);
+    /// assert_eq!(as_.asn(), 204867);
+    /// assert_eq!(as_.name(), "Lightning Wire Labs GmbH");
     ///
     /// # Ok::<(), libloc::OpenError>(())
     /// ```
-    ///
-    /// [anycast]: https://en.wikipedia.org/wiki/Anycast
-    pub fn addrs(&self) -> IpNet {
-        self.addrs
-    }
-}
-
-impl<'a> From<NetworkV4<'a>> for Network<'a> {
-    fn from(network: NetworkV4<'a>) -> Network<'a> {
-        Network {
-            inner: network.inner,
-            addrs: network.addrs.into(),
+    pub fn to_owned(&self) -> AsOwned {
+        AsOwned {
+            asn: self.asn,
+            name: self.name_lossy().into_owned(),
         }
     }
 }
 
-impl<'a> From<NetworkV6<'a>> for Network<'a> {
-    fn from(network: NetworkV6<'a>) -> Network<'a> {
-        Network {
-            inner: network.inner,
-            addrs: network.addrs.into(),
+impl<'a> NetworkInner<'a> {
+    fn from(inner: &LocationsInner<'a>, network: &'a format::Network) -> NetworkInner<'a> {
+        NetworkInner {
+            country_code: &network.country_code,
+            asn: network.asn.get(),
+            flags: network.flags.get(),
+            // The format has no per-network timestamps yet, fall back to the
+            // database creation time. Once it does, read it from `network`.
+            updated_at: inner.header.created_at.get(),
         }
     }
-}
-
-impl<'a> NetworkV4<'a> {
-    /// See [`Network::country_code`].
-    pub fn country_code(&self) -> &'a str {
-        self.inner.country_code
+    fn country_code(&self) -> &'a str {
+        from_utf8(self.country_code, "network country code")
     }
-    /// See [`Network::asn`].
-    pub fn asn(&self) -> u32 {
-        self.inner.asn
+    fn autonomous_system_number(&self) -> Option<u32> {
+        if self.asn == 0 {
+            return None;
+        }
+        Some(self.asn)
     }
-    /// See [`Network::is_anonymous_proxy`].
-    pub fn is_anonymous_proxy(&self) -> bool {
-        self.inner.flags & format::NETWORK_FLAG_ANONYMOUS_PROXY != 0
+    fn country(&self) -> Option<&'a str> {
+        if self.country_code == b"XX" {
+            return None;
+        }
+        Some(self.country_code())
     }
-    /// See [`Network::is_satellite_provider`].
-    pub fn is_satellite_provider(&self) -> bool {
-        self.inner.flags & format::NETWORK_FLAG_SATTELITE_PROVIDER != 0
+    fn to_owned(&self, addrs: IpNet) -> NetworkOwned {
+        NetworkOwned {
+            addrs,
+            country_code: String::from_utf8_lossy(self.country_code).into_owned(),
+            asn: self.asn,
+            flags: self.flags,
+        }
     }
-    /// See [`Network::is_anycast`].
-    pub fn is_anycast(&self) -> bool {
-        self.inner.flags & format::NETWORK_FLAG_ANYCAST != 0
+    fn flags(&self) -> NetworkFlags {
+        NetworkFlags::from_bits(self.flags)
     }
-    /// See [`Network::is_drop`].
-    pub fn is_drop(&self) -> bool {
-        self.inner.flags & format::NETWORK_FLAG_DROP != 0
+    fn unknown_flags(&self) -> u16 {
+        self.flags & !NetworkFlags::all().bits()
     }
-    /// See [`Network::addrs`].
-    pub fn addrs(&self) -> Ipv4Net {
-        self.addrs
+    fn flag_names(&self) -> Vec<&'static str> {
+        [
+            (NetworkFlags::ANONYMOUS_PROXY, "anonymous_proxy"),
+            (NetworkFlags::SATELLITE_PROVIDER, "satellite_provider"),
+            (NetworkFlags::ANYCAST, "anycast"),
+            (NetworkFlags::DROP, "drop"),
+        ]
+        .into_iter()
+        .filter(|&(flag, _)| self.flags().contains(flag))
+        .map(|(_, name)| name)
+        .collect()
     }
 }
 
-impl<'a> NetworkV6<'a> {
-    /// See [`Network::country_code`].
+impl<'a> Network<'a> {
+    /// The [ISO 3166-1 alpha-2] country code of the country associated with
+    /// this network.
+    ///
+    /// `"XX"` if unknown. Use [`Network::country`] to get `None` instead.
+    ///
+    /// [ISO 3166-1 alpha-2]: https://en.wikipedia.org/wiki/ISO_3166-1_alpha-2
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let network: libloc::Network = locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap();
+    /// assert_eq!(network.country_code(), "DE");
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
     pub fn country_code(&self) -> &'a str {
-        self.inner.country_code
-    }
-    /// See [`Network::asn`].
-    pub fn asn(&self) -> u32 {
-        self.inner.asn
-    }
-    /// See [`Network::is_anonymous_proxy`].
-    pub fn is_anonymous_proxy(&self) -> bool {
-        self.inner.flags & format::NETWORK_FLAG_ANONYMOUS_PROXY != 0
-    }
-    /// See [`Network::is_satellite_provider`].
-    pub fn is_satellite_provider(&self) -> bool {
-        self.inner.flags & format::NETWORK_FLAG_SATTELITE_PROVIDER != 0
-    }
-    /// See [`Network::is_anycast`].
-    pub fn is_anycast(&self) -> bool {
-        self.inner.flags & format::NETWORK_FLAG_ANYCAST != 0
-    }
-    /// See [`Network::is_drop`].
-    pub fn is_drop(&self) -> bool {
-        self.inner.flags & format::NETWORK_FLAG_DROP != 0
-    }
-    /// See [`Network::addrs`].
-    pub fn addrs(&self) -> Ipv6Net {
-        self.addrs
-    }
-}
-
-impl<'a> Country<'a> {
-    fn from(inner: &LocationsInner<'a>, country: &'a format::Country) -> Country<'a> {
-        Country {
-            code: str::from_utf8(&country.code).unwrap_or_else(|e| {
-                panic!("corrupt libloc db: invalid UTF-8 in country code: {}", e);
-            }),
-            continent_code: str::from_utf8(&country.continent_code).unwrap_or_else(|e| {
-                panic!(
-                    "corrupt libloc db: invalid UTF-8 in country continent code: {}",
-                    e,
-                );
-            }),
-            name: inner.string(country.name),
-        }
+        self.inner.country_code()
     }
-    /// The [ISO 3166-1 alpha-2] code of the country.
+    /// The [ISO 3166-1 alpha-2] country code of the country associated with
+    /// this network, `None` if unknown.
     ///
-    /// It consists of two uppercase latin letters.
+    /// Unlike [`Network::country_code`], this maps the placeholder code
+    /// `"XX"` for unknown countries to `None`, so callers don't need to
+    /// special-case it. Prefer this unless you need the raw code.
     ///
     /// [ISO 3166-1 alpha-2]: https://en.wikipedia.org/wiki/ISO_3166-1_alpha-2
-    pub fn code(&self) -> &'a str {
-        self.code
-    }
-    /// The [ISO 3166] code of the continent the country resides in.
     ///
-    /// - `"AF"` for Africa.
-    /// - `"AN"` for Antarctica.
-    /// - `"AS"` for Asia.
-    /// - `"EU"` for Europe.
-    /// - `"NA"` for North America.
-    /// - `"OC"` for Oceania.
-    /// - `"SA"` for South America.
+    /// ```
+    /// use libloc::TestDatabaseBuilder;
     ///
-    /// [ISO 3166]: https://en.wikipedia.org/wiki/ISO_3166
-    pub fn continent_code(&self) -> &'a str {
-        self.continent_code
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network("192.0.2.0/24", 64496, "DE")
+    ///     .add_network("198.51.100.0/24", 64496, "XX")
+    ///     .build();
+    /// let network = locations.lookup("192.0.2.1".parse().unwrap()).unwrap();
+    /// assert_eq!(network.country(), Some("DE"));
+    /// let network = locations.lookup("198.51.100.1".parse().unwrap()).unwrap();
+    /// assert_eq!(network.country(), None);
+    /// assert_eq!(network.country_code(), "XX");
+    /// ```
+    pub fn country(&self) -> Option<&'a str> {
+        self.inner.country()
     }
-    /// The human-readable name of the country in English.
-    pub fn name(&self) -> &'a str {
-        self.name
+    /// The country code of this network, replacing invalid UTF-8.
+    ///
+    /// Unlike [`Network::country_code`], this never panics on invalid UTF-8
+    /// in a corrupt database, see [`String::from_utf8_lossy`].
+    pub fn country_code_lossy(&self) -> Cow<'a, str> {
+        String::from_utf8_lossy(self.inner.country_code)
+    }
+    /// The [ASN] of this network.
+    ///
+    /// 0 if unknown. Use [`Network::autonomous_system_number`] to get `None`
+    /// instead.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let network: libloc::Network = locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap();
+    /// assert_eq!(network.asn(), 204867);
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    ///
+    /// [ASN]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
+    pub fn asn(&self) -> u32 {
+        self.inner.asn
+    }
+    /// The [ASN] of this network, `None` if unknown.
+    ///
+    /// Unlike [`Network::asn`], this maps the reserved ASN 0 for networks
+    /// without an AS assignment to `None`.
+    ///
+    /// ```
+    /// use libloc::TestDatabaseBuilder;
+    ///
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network("192.0.2.0/24", 64496, "DE")
+    ///     .add_network("198.51.100.0/24", 0, "DE")
+    ///     .build();
+    /// let network = locations.lookup("192.0.2.1".parse().unwrap()).unwrap();
+    /// assert_eq!(network.autonomous_system_number(), Some(64496));
+    /// let network = locations.lookup("198.51.100.1".parse().unwrap()).unwrap();
+    /// assert_eq!(network.autonomous_system_number(), None);
+    /// assert_eq!(network.asn(), 0);
+    /// ```
+    ///
+    /// [ASN]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
+    pub fn autonomous_system_number(&self) -> Option<u32> {
+        self.inner.autonomous_system_number()
+    }
+    /// Whether the network hosts anonymous proxies.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let network: libloc::Network = locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap();
+    /// assert_eq!(network.is_anonymous_proxy(), false);
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    ///
+    /// [ASN]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
+    pub fn is_anonymous_proxy(&self) -> bool {
+        self.flags().contains(NetworkFlags::ANONYMOUS_PROXY)
+    }
+    /// Whether the network is a satellite provider.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let network: libloc::Network = locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap();
+    /// assert_eq!(network.is_satellite_provider(), false);
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    ///
+    /// [ASN]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
+    pub fn is_satellite_provider(&self) -> bool {
+        self.flags().contains(NetworkFlags::SATELLITE_PROVIDER)
+    }
+    /// Whether the network consists of [anycast] addresses.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let network: libloc::Network = locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap();
+    /// assert_eq!(network.is_anycast(), true);
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    ///
+    /// [anycast]: https://en.wikipedia.org/wiki/Anycast
+    pub fn is_anycast(&self) -> bool {
+        self.flags().contains(NetworkFlags::ANYCAST)
+    }
+    /// Whether traffic from the network should be dropped.
+    ///
+    /// This marks networks that are hijacked or used by spammers, e.g.
+    /// those on the [Spamhaus DROP] list.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let network: libloc::Network = locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap();
+    /// assert_eq!(network.is_drop(), false);
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    ///
+    /// [Spamhaus DROP]: https://www.spamhaus.org/blocklists/do-not-route-or-peer/
+    pub fn is_drop(&self) -> bool {
+        self.flags().contains(NetworkFlags::DROP)
+    }
+    /// The flags of the network.
+    ///
+    /// Unlike the `is_*` functions, this also exposes flags that this crate
+    /// doesn't know yet.
+    ///
+    /// ```
+    /// use libloc::{NetworkFlags, TestDatabaseBuilder};
+    ///
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network_with_flags("192.0.2.0/24", 64496, "DE", NetworkFlags::from_bits(0x001c))
+    ///     .build();
+    /// let flags = locations.lookup("192.0.2.1".parse().unwrap()).unwrap().flags();
+    /// assert!(flags.contains(NetworkFlags::DROP | NetworkFlags::ANYCAST));
+    /// assert!(!flags.contains(NetworkFlags::ANYCAST | NetworkFlags::ANONYMOUS_PROXY));
+    /// assert_eq!(flags.bits(), 0x001c);
+    /// ```
+    pub fn flags(&self) -> NetworkFlags {
+        self.inner.flags()
+    }
+    /// The names of the flags set on this network.
+    ///
+    /// The names are `"anonymous_proxy"`, `"satellite_provider"`,
+    /// `"anycast"` and `"drop"`, in this order. Unknown flags are left out.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let network = locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap();
+    /// assert_eq!(network.flag_names(), ["anycast"]);
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn flag_names(&self) -> Vec<&'static str> {
+        self.inner.flag_names()
+    }
+    /// The flag bits set on this network that this crate doesn't know.
+    ///
+    /// A non-zero result means that the database uses flags introduced after
+    /// this version of the crate, which might warrant an upgrade.
+    ///
+    /// ```
+    /// use libloc::{NetworkFlags, TestDatabaseBuilder};
+    ///
+    /// let flags = NetworkFlags::ANYCAST | NetworkFlags::from_bits(0x0010);
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network_with_flags("192.0.2.0/24", 64496, "DE", flags)
+    ///     .build();
+    /// let network = locations.lookup("192.0.2.1".parse().unwrap()).unwrap();
+    /// assert!(network.is_anycast());
+    /// assert_eq!(network.unknown_flags(), 0x0010);
+    /// ```
+    pub fn unknown_flags(&self) -> u16 {
+        self.inner.unknown_flags()
+    }
+    /// The time this network was last updated.
+    ///
+    /// The database format does not store per-network timestamps yet, so this
+    /// is currently always the database creation time, see
+    /// [`Locations::created_at`].
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let network: libloc::Network = locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap();
+    /// assert_eq!(network.updated_at(), locations.created_at());
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    #[cfg(feature = "time")]
+    pub fn updated_at(&self) -> chrono::DateTime<chrono::offset::Utc> {
+        timestamp(self.inner.updated_at, "network updated_at")
+    }
+    /// All the addresses belonging to this particular network.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let network: libloc::Network = locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap();
+    /// assert_eq!(network.addrs().to_string(), "2a07:1c44:5800::/40");
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    ///
+    /// [anycast]: https://en.wikipedia.org/wiki/Anycast
+    pub fn addrs(&self) -> IpNet {
+        self.addrs
+    }
+    /// Copy the network information out of the database.
+    ///
+    /// The result can outlive the [`Locations`] it was looked up in.
+    ///
+    /// ```
+    /// use libloc::{Locations, NetworkOwned};
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let networks: Vec<NetworkOwned> = locations.networks().map(|n| n.to_owned()).collect();
+    /// drop(locations);
+    /// assert_eq!(networks.len(), 1);
+    /// assert_eq!(networks[0].addrs().to_string(), "2a07:1c44:5800::/40");
+    /// assert_eq!(networks[0].country_code(), "DE");
+    /// assert_eq!(networks[0].asn(), 204867);
+    /// assert!(networks[0].is_anycast());
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn to_owned(&self) -> NetworkOwned {
+        self.inner.to_owned(self.addrs)
+    }
+    /// The first address of the network.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let network: libloc::Network = locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap();
+    /// assert_eq!(network.first_address().to_string(), "2a07:1c44:5800::");
+    /// assert_eq!(network.last_address().to_string(), "2a07:1c44:58ff:ffff:ffff:ffff:ffff:ffff");
+    /// assert_eq!(network.host_count(), 1 << 88);
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn first_address(&self) -> IpAddr {
+        self.addrs.network()
+    }
+    /// The last address of the network.
+    ///
+    /// See [`Network::first_address`].
+    pub fn last_address(&self) -> IpAddr {
+        self.addrs.broadcast()
+    }
+    /// The number of addresses in the network.
+    ///
+    /// This includes the first and last address. Saturates at [`u128::MAX`]
+    /// for a network covering the whole IPv6 address space.
+    pub fn host_count(&self) -> u128 {
+        host_count(self.addrs.max_prefix_len() - self.addrs.prefix_len())
+    }
+    /// Whether the network's prefix is exactly the given one.
+    ///
+    /// Both the address and the prefix length must match. Prefixes of a
+    /// different address family never match, e.g. an IPv4-mapped IPv6
+    /// prefix is not considered equal to the corresponding IPv4 prefix.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let network: libloc::Network = locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap();
+    /// assert!(network.has_prefix("2a07:1c44:5800::/40".parse().unwrap()));
+    /// assert!(!network.has_prefix("2a07:1c44:5800::/48".parse().unwrap()));
+    /// assert!(!network.has_prefix("2a07:1c44:5800::1/40".parse().unwrap()));
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn has_prefix(&self, net: IpNet) -> bool {
+        match (self.addrs, net) {
+            (IpNet::V4(addrs), IpNet::V4(net)) => addrs == net,
+            (IpNet::V6(addrs), IpNet::V6(net)) => addrs == net,
+            _ => false,
+        }
+    }
+    /// The number of bits of a looked up address that were not needed to
+    /// find this network.
+    ///
+    /// This is the width of the address family minus the prefix length of
+    /// the network, i.e. the number of host bits.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let network: libloc::Network = locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap();
+    /// assert_eq!(network.unmatched_bits(), 88);
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn unmatched_bits(&self) -> u8 {
+        self.addrs.max_prefix_len() - self.addrs.prefix_len()
     }
 }
 
-/// A database in libloc format. **Main struct of this crate.**
-pub struct Locations {
-    inner: Yoke<LocationsInner<'static>, Mmap>,
-}
-
-#[cfg_attr(feature = "verified", derive(yoke_derive::Yokeable))]
-struct LocationsInner<'a> {
-    header: &'a format::Header,
-    as_: &'a [format::As],
-    networks: &'a [format::Network],
-    network_nodes: &'a [format::NetworkNode],
-    countries: &'a [format::Country],
-    string_pool: &'a [u8],
-    ipv4_network_node: Option<u32>,
+impl<'a> From<NetworkV4<'a>> for Network<'a> {
+    fn from(network: NetworkV4<'a>) -> Network<'a> {
+        Network {
+            inner: network.inner,
+            addrs: network.addrs.into(),
+        }
+    }
 }
 
-#[cfg(not(feature = "verified"))]
-unsafe impl<'a> yoke::Yokeable<'a> for LocationsInner<'static> {
-    type Output = LocationsInner<'a>;
-    fn transform(&'a self) -> &'a LocationsInner<'a> {
-        self
+impl<'a> From<NetworkV6<'a>> for Network<'a> {
+    fn from(network: NetworkV6<'a>) -> Network<'a> {
+        Network {
+            inner: network.inner,
+            addrs: network.addrs.into(),
+        }
     }
-    fn transform_owned(self) -> LocationsInner<'a> {
-        self
+}
+
+#[cfg(feature = "ipnetwork")]
+fn to_ipnetwork(addrs: IpNet) -> ipnetwork::IpNetwork {
+    match addrs {
+        IpNet::V4(addrs) => ipnetwork::Ipv4Network::new(addrs.network(), addrs.prefix_len())
+            .unwrap()
+            .into(),
+        IpNet::V6(addrs) => ipnetwork::Ipv6Network::new(addrs.network(), addrs.prefix_len())
+            .unwrap()
+            .into(),
     }
-    unsafe fn make(from: LocationsInner<'a>) -> LocationsInner<'static> {
-        // We're just doing mem::transmute() here, however Rust is
-        // not smart enough to realize that Bar<'a> and Bar<'static> are of
-        // the same size, so instead we use transmute_copy
-        assert!(
-            std::mem::size_of::<LocationsInner<'a>>()
-                == std::mem::size_of::<LocationsInner<'static>>()
-        );
-        let ptr: *const LocationsInner<'static> = (&from as *const LocationsInner<'a>).cast();
-        std::mem::forget(from);
-        std::ptr::read(ptr)
+}
+
+#[cfg(feature = "ipnetwork")]
+fn from_ipnetwork(net: ipnetwork::IpNetwork) -> IpNet {
+    match net {
+        ipnetwork::IpNetwork::V4(net) => Ipv4Net::new(net.ip(), net.prefix()).unwrap().into(),
+        ipnetwork::IpNetwork::V6(net) => Ipv6Net::new(net.ip(), net.prefix()).unwrap().into(),
     }
-    fn transform_mut<F: FnOnce(&'a mut LocationsInner<'a>) + 'static>(&'a mut self, f: F) {
-        unsafe {
-            f(std::mem::transmute::<
-                &mut LocationsInner<'static>,
-                &mut LocationsInner<'a>,
-            >(self))
+}
+
+#[cfg(feature = "ipnetwork")]
+impl<'a> From<&Network<'a>> for ipnetwork::IpNetwork {
+    fn from(network: &Network<'a>) -> ipnetwork::IpNetwork {
+        to_ipnetwork(network.addrs)
+    }
+}
+
+/// ```
+/// use ipnetwork::IpNetwork;
+/// use libloc::Locations;
+///
+/// let locations = Locations::open("example-location.db")?;
+/// let network = locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap();
+/// let net: IpNetwork = (&network.to_owned()).into();
+/// assert_eq!(net, "2a07:1c44:5800::/40".parse::<IpNetwork>().unwrap());
+///
+/// # Ok::<(), libloc::OpenError>(())
+/// ```
+#[cfg(feature = "ipnetwork")]
+impl From<&NetworkOwned> for ipnetwork::IpNetwork {
+    fn from(network: &NetworkOwned) -> ipnetwork::IpNetwork {
+        to_ipnetwork(network.addrs)
+    }
+}
+
+#[cfg(feature = "ipnetwork")]
+impl<'a> From<&NetworkV4<'a>> for ipnetwork::Ipv4Network {
+    fn from(network: &NetworkV4<'a>) -> ipnetwork::Ipv4Network {
+        ipnetwork::Ipv4Network::new(network.addrs.network(), network.addrs.prefix_len()).unwrap()
+    }
+}
+
+#[cfg(feature = "ipnetwork")]
+impl<'a> From<&NetworkV6<'a>> for ipnetwork::Ipv6Network {
+    fn from(network: &NetworkV6<'a>) -> ipnetwork::Ipv6Network {
+        ipnetwork::Ipv6Network::new(network.addrs.network(), network.addrs.prefix_len()).unwrap()
+    }
+}
+
+impl<'a> NetworkV4<'a> {
+    /// See [`Network::country_code`].
+    pub fn country_code(&self) -> &'a str {
+        self.inner.country_code()
+    }
+    /// See [`Network::country`].
+    pub fn country(&self) -> Option<&'a str> {
+        self.inner.country()
+    }
+    /// See [`Network::country_code_lossy`].
+    pub fn country_code_lossy(&self) -> Cow<'a, str> {
+        String::from_utf8_lossy(self.inner.country_code)
+    }
+    /// See [`Network::asn`].
+    pub fn asn(&self) -> u32 {
+        self.inner.asn
+    }
+    /// See [`Network::autonomous_system_number`].
+    pub fn autonomous_system_number(&self) -> Option<u32> {
+        self.inner.autonomous_system_number()
+    }
+    /// See [`Network::is_anonymous_proxy`].
+    pub fn is_anonymous_proxy(&self) -> bool {
+        self.flags().contains(NetworkFlags::ANONYMOUS_PROXY)
+    }
+    /// See [`Network::is_satellite_provider`].
+    pub fn is_satellite_provider(&self) -> bool {
+        self.flags().contains(NetworkFlags::SATELLITE_PROVIDER)
+    }
+    /// See [`Network::is_anycast`].
+    pub fn is_anycast(&self) -> bool {
+        self.flags().contains(NetworkFlags::ANYCAST)
+    }
+    /// See [`Network::is_drop`].
+    pub fn is_drop(&self) -> bool {
+        self.flags().contains(NetworkFlags::DROP)
+    }
+    /// See [`Network::flags`].
+    pub fn flags(&self) -> NetworkFlags {
+        self.inner.flags()
+    }
+    /// See [`Network::flag_names`].
+    pub fn flag_names(&self) -> Vec<&'static str> {
+        self.inner.flag_names()
+    }
+    /// See [`Network::unknown_flags`].
+    pub fn unknown_flags(&self) -> u16 {
+        self.inner.unknown_flags()
+    }
+    /// See [`Network::updated_at`].
+    #[cfg(feature = "time")]
+    pub fn updated_at(&self) -> chrono::DateTime<chrono::offset::Utc> {
+        timestamp(self.inner.updated_at, "network updated_at")
+    }
+    /// See [`Network::addrs`].
+    pub fn addrs(&self) -> Ipv4Net {
+        self.addrs
+    }
+    /// See [`Network::to_owned`].
+    pub fn to_owned(&self) -> NetworkOwned {
+        self.inner.to_owned(self.addrs.into())
+    }
+    /// See [`Network::first_address`].
+    pub fn first_address(&self) -> Ipv4Addr {
+        self.addrs.network()
+    }
+    /// See [`Network::last_address`].
+    pub fn last_address(&self) -> Ipv4Addr {
+        self.addrs.broadcast()
+    }
+    /// See [`Network::host_count`].
+    pub fn host_count(&self) -> u128 {
+        host_count(self.addrs.max_prefix_len() - self.addrs.prefix_len())
+    }
+    /// See [`Network::has_prefix`].
+    pub fn has_prefix(&self, net: Ipv4Net) -> bool {
+        self.addrs == net
+    }
+    /// See [`Network::unmatched_bits`].
+    pub fn unmatched_bits(&self) -> u8 {
+        self.addrs.max_prefix_len() - self.addrs.prefix_len()
+    }
+}
+
+impl<'a> NetworkV6<'a> {
+    /// See [`Network::country_code`].
+    pub fn country_code(&self) -> &'a str {
+        self.inner.country_code()
+    }
+    /// See [`Network::country`].
+    pub fn country(&self) -> Option<&'a str> {
+        self.inner.country()
+    }
+    /// See [`Network::country_code_lossy`].
+    pub fn country_code_lossy(&self) -> Cow<'a, str> {
+        String::from_utf8_lossy(self.inner.country_code)
+    }
+    /// See [`Network::asn`].
+    pub fn asn(&self) -> u32 {
+        self.inner.asn
+    }
+    /// See [`Network::autonomous_system_number`].
+    pub fn autonomous_system_number(&self) -> Option<u32> {
+        self.inner.autonomous_system_number()
+    }
+    /// See [`Network::is_anonymous_proxy`].
+    pub fn is_anonymous_proxy(&self) -> bool {
+        self.flags().contains(NetworkFlags::ANONYMOUS_PROXY)
+    }
+    /// See [`Network::is_satellite_provider`].
+    pub fn is_satellite_provider(&self) -> bool {
+        self.flags().contains(NetworkFlags::SATELLITE_PROVIDER)
+    }
+    /// See [`Network::is_anycast`].
+    pub fn is_anycast(&self) -> bool {
+        self.flags().contains(NetworkFlags::ANYCAST)
+    }
+    /// See [`Network::is_drop`].
+    pub fn is_drop(&self) -> bool {
+        self.flags().contains(NetworkFlags::DROP)
+    }
+    /// See [`Network::flags`].
+    pub fn flags(&self) -> NetworkFlags {
+        self.inner.flags()
+    }
+    /// See [`Network::flag_names`].
+    pub fn flag_names(&self) -> Vec<&'static str> {
+        self.inner.flag_names()
+    }
+    /// See [`Network::unknown_flags`].
+    pub fn unknown_flags(&self) -> u16 {
+        self.inner.unknown_flags()
+    }
+    /// See [`Network::updated_at`].
+    #[cfg(feature = "time")]
+    pub fn updated_at(&self) -> chrono::DateTime<chrono::offset::Utc> {
+        timestamp(self.inner.updated_at, "network updated_at")
+    }
+    /// See [`Network::addrs`].
+    pub fn addrs(&self) -> Ipv6Net {
+        self.addrs
+    }
+    /// See [`Network::to_owned`].
+    pub fn to_owned(&self) -> NetworkOwned {
+        self.inner.to_owned(self.addrs.into())
+    }
+    /// See [`Network::first_address`].
+    pub fn first_address(&self) -> Ipv6Addr {
+        self.addrs.network()
+    }
+    /// See [`Network::last_address`].
+    pub fn last_address(&self) -> Ipv6Addr {
+        self.addrs.broadcast()
+    }
+    /// See [`Network::host_count`].
+    pub fn host_count(&self) -> u128 {
+        host_count(self.addrs.max_prefix_len() - self.addrs.prefix_len())
+    }
+    /// See [`Network::has_prefix`].
+    pub fn has_prefix(&self, net: Ipv6Net) -> bool {
+        self.addrs == net
+    }
+    /// See [`Network::unmatched_bits`].
+    pub fn unmatched_bits(&self) -> u8 {
+        self.addrs.max_prefix_len() - self.addrs.prefix_len()
+    }
+}
+
+impl<'a> Country<'a> {
+    fn from(inner: &LocationsInner<'a>, country: &'a format::Country) -> Country<'a> {
+        Country {
+            code: &country.code,
+            continent_code: &country.continent_code,
+            name: inner.pool_str(country.name),
+        }
+    }
+    /// The [ISO 3166-1 alpha-2] code of the country.
+    ///
+    /// It consists of two uppercase latin letters.
+    ///
+    /// [ISO 3166-1 alpha-2]: https://en.wikipedia.org/wiki/ISO_3166-1_alpha-2
+    pub fn code(&self) -> &'a str {
+        from_utf8(self.code, "country code")
+    }
+    /// The code of the country, replacing invalid UTF-8.
+    ///
+    /// Unlike [`Country::code`], this never panics on invalid UTF-8 in a
+    /// corrupt database, see [`String::from_utf8_lossy`].
+    pub fn code_lossy(&self) -> Cow<'a, str> {
+        String::from_utf8_lossy(self.code)
+    }
+    /// The [ISO 3166] code of the continent the country resides in.
+    ///
+    /// - `"AF"` for Africa.
+    /// - `"AN"` for Antarctica.
+    /// - `"AS"` for Asia.
+    /// - `"EU"` for Europe.
+    /// - `"NA"` for North America.
+    /// - `"OC"` for Oceania.
+    /// - `"SA"` for South America.
+    ///
+    /// [ISO 3166]: https://en.wikipedia.org/wiki/ISO_3166
+    pub fn continent_code(&self) -> &'a str {
+        from_utf8(self.continent_code, "country continent code")
+    }
+    /// The continent code of the country, replacing invalid UTF-8.
+    ///
+    /// Unlike [`Country::continent_code`], this never panics on invalid UTF-8
+    /// in a corrupt database, see [`String::from_utf8_lossy`].
+    pub fn continent_code_lossy(&self) -> Cow<'a, str> {
+        String::from_utf8_lossy(self.continent_code)
+    }
+    /// The human-readable name of the country in English.
+    pub fn name(&self) -> &'a str {
+        from_utf8(self.name.bytes(), "country name")
+    }
+    /// The name of the country, replacing invalid UTF-8.
+    ///
+    /// Unlike [`Country::name`], this never panics on invalid UTF-8 in a
+    /// corrupt database, see [`String::from_utf8_lossy`]. Like for
+    /// [`As::name_lossy`], invalid string references yield an empty name.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// assert_eq!(locations.country("DE").unwrap().name_lossy(), "Germany");
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn name_lossy(&self) -> Cow<'a, str> {
+        String::from_utf8_lossy(self.name.bytes_lossy())
+    }
+    /// Copy the country information out of the database.
+    ///
+    /// The result can outlive the [`Locations`] it was looked up in. Invalid
+    /// UTF-8 is replaced, see [`String::from_utf8_lossy`].
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let country = locations.country("DE").unwrap().to_owned();
+    /// drop(locations);
+    /// assert_eq!(country.code(), "DE");
+    /// assert_eq!(country.continent_code(), "EU");
+    /// assert_eq!(country.name(), "Germany");
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn to_owned(&self) -> CountryOwned {
+        CountryOwned {
+            code: self.code_lossy().into_owned(),
+            continent_code: self.continent_code_lossy().into_owned(),
+            name: self.name_lossy().into_owned(),
+        }
+    }
+}
+
+impl NetworkFlags {
+    /// The network hosts anonymous proxies, see
+    /// [`Network::is_anonymous_proxy`].
+    pub const ANONYMOUS_PROXY: NetworkFlags = NetworkFlags(format::NETWORK_FLAG_ANONYMOUS_PROXY);
+    /// The network is a satellite provider, see
+    /// [`Network::is_satellite_provider`].
+    pub const SATELLITE_PROVIDER: NetworkFlags =
+        NetworkFlags(format::NETWORK_FLAG_SATTELITE_PROVIDER);
+    /// The network consists of anycast addresses, see [`Network::is_anycast`].
+    pub const ANYCAST: NetworkFlags = NetworkFlags(format::NETWORK_FLAG_ANYCAST);
+    /// Traffic from the network should be dropped, see [`Network::is_drop`].
+    pub const DROP: NetworkFlags = NetworkFlags(format::NETWORK_FLAG_DROP);
+
+    /// The empty set of flags.
+    pub const fn empty() -> NetworkFlags {
+        NetworkFlags(0)
+    }
+    /// All flags known to this crate.
+    pub const fn all() -> NetworkFlags {
+        NetworkFlags(
+            NetworkFlags::ANONYMOUS_PROXY.0
+                | NetworkFlags::SATELLITE_PROVIDER.0
+                | NetworkFlags::ANYCAST.0
+                | NetworkFlags::DROP.0,
+        )
+    }
+    /// The flags with the given bits, including unknown ones.
+    pub const fn from_bits(bits: u16) -> NetworkFlags {
+        NetworkFlags(bits)
+    }
+    /// The bits of the flags, as stored in the database.
+    pub const fn bits(self) -> u16 {
+        self.0
+    }
+    /// Whether no flag is set.
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+    /// Whether all flags of `other` are set.
+    pub const fn contains(self, other: NetworkFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+    /// Whether any flag of `other` is set.
+    pub const fn intersects(self, other: NetworkFlags) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl ops::BitOr for NetworkFlags {
+    type Output = NetworkFlags;
+    fn bitor(self, other: NetworkFlags) -> NetworkFlags {
+        NetworkFlags(self.0 | other.0)
+    }
+}
+
+impl ops::BitOrAssign for NetworkFlags {
+    fn bitor_assign(&mut self, other: NetworkFlags) {
+        self.0 |= other.0;
+    }
+}
+
+impl ops::BitAnd for NetworkFlags {
+    type Output = NetworkFlags;
+    fn bitand(self, other: NetworkFlags) -> NetworkFlags {
+        NetworkFlags(self.0 & other.0)
+    }
+}
+
+impl ops::BitAndAssign for NetworkFlags {
+    fn bitand_assign(&mut self, other: NetworkFlags) {
+        self.0 &= other.0;
+    }
+}
+
+impl AsOwned {
+    /// See [`As::asn`].
+    pub fn asn(&self) -> u32 {
+        self.asn
+    }
+    /// See [`As::name`].
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl NetworkOwned {
+    /// See [`Network::addrs`].
+    pub fn addrs(&self) -> IpNet {
+        self.addrs
+    }
+    /// See [`Network::country_code`].
+    pub fn country_code(&self) -> &str {
+        &self.country_code
+    }
+    /// See [`Network::asn`].
+    pub fn asn(&self) -> u32 {
+        self.asn
+    }
+    /// See [`Network::is_anonymous_proxy`].
+    pub fn is_anonymous_proxy(&self) -> bool {
+        self.flags().contains(NetworkFlags::ANONYMOUS_PROXY)
+    }
+    /// See [`Network::is_satellite_provider`].
+    pub fn is_satellite_provider(&self) -> bool {
+        self.flags().contains(NetworkFlags::SATELLITE_PROVIDER)
+    }
+    /// See [`Network::is_anycast`].
+    pub fn is_anycast(&self) -> bool {
+        self.flags().contains(NetworkFlags::ANYCAST)
+    }
+    /// See [`Network::is_drop`].
+    pub fn is_drop(&self) -> bool {
+        self.flags().contains(NetworkFlags::DROP)
+    }
+    /// See [`Network::flags`].
+    pub fn flags(&self) -> NetworkFlags {
+        NetworkFlags::from_bits(self.flags)
+    }
+}
+
+impl CountryOwned {
+    /// See [`Country::code`].
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+    /// See [`Country::continent_code`].
+    pub fn continent_code(&self) -> &str {
+        &self.continent_code
+    }
+    /// See [`Country::name`].
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<'a> AsFootprint<'a> {
+    /// The AS.
+    pub fn as_(&self) -> &As<'a> {
+        &self.as_
+    }
+    /// The number of networks of the AS in the database.
+    ///
+    /// Networks nested within other networks of the AS are counted
+    /// separately.
+    pub fn network_count(&self) -> usize {
+        self.network_count
+    }
+    /// The number of IPv4 addresses in the networks of the AS.
+    ///
+    /// Addresses in nested networks are counted once per network.
+    pub fn ipv4_address_count(&self) -> u64 {
+        self.ipv4_address_count
+    }
+    /// The number of IPv6 addresses in the networks of the AS.
+    ///
+    /// Addresses in nested networks are counted once per network. Saturates
+    /// at [`u128::MAX`] for a network covering the whole IPv6 address space.
+    pub fn ipv6_address_count(&self) -> u128 {
+        self.ipv6_address_count
+    }
+}
+
+impl<'a> QueryMatch<'a> {
+    /// The address that was looked up.
+    pub fn query(&self) -> IpAddr {
+        self.query
+    }
+    /// The network containing the address.
+    pub fn network(&self) -> &Network<'a> {
+        &self.network
+    }
+    /// Take the network, discarding the address.
+    pub fn into_network(self) -> Network<'a> {
+        self.network
+    }
+}
+
+impl<'a> LookupDetails<'a> {
+    /// The network containing the address.
+    pub fn network(&self) -> &Network<'a> {
+        &self.network
+    }
+    /// Take the network, discarding the details.
+    pub fn into_network(self) -> Network<'a> {
+        self.network
+    }
+    /// The prefix length of the network.
+    pub fn prefix_len(&self) -> u8 {
+        self.network.addrs().prefix_len()
+    }
+    /// The number of address bits consumed while walking the network tree.
+    ///
+    /// This is at least [`LookupDetails::prefix_len`]. It is larger if the
+    /// tree holds more specific networks next to the address, which don't
+    /// contain it.
+    pub fn walked_bits(&self) -> u8 {
+        self.walked_bits
+    }
+    /// Whether the tree walk consumed all bits of the address, 32 for IPv4
+    /// and 128 for IPv6 addresses.
+    pub fn is_full_walk(&self) -> bool {
+        self.walked_bits == self.address_bits
+    }
+    /// Whether the network is where the tree walk ended.
+    ///
+    /// If not, the network is a broader network covering the address,
+    /// found because the tree branched towards more specific networks that
+    /// don't contain the address.
+    pub fn is_exact(&self) -> bool {
+        self.prefix_len() == self.walked_bits
+    }
+}
+
+impl<'a> Description<'a> {
+    /// The network containing the address.
+    pub fn network(&self) -> &Network<'a> {
+        &self.network
+    }
+    /// The AS of the network, see [`Locations::as_of`].
+    ///
+    /// `None` for AS0 or if the AS is not in the database.
+    pub fn as_(&self) -> Option<&As<'a>> {
+        self.as_.as_ref()
+    }
+    /// The country of the network, see [`Locations::country_of`].
+    ///
+    /// `None` for the unknown country `XX` or if the country is not in the
+    /// database.
+    pub fn country(&self) -> Option<&Country<'a>> {
+        self.country.as_ref()
+    }
+}
+
+/// A database in libloc format. **Main struct of this crate.**
+///
+/// Cloning is cheap: clones share the memory mapping or buffer holding the
+/// database through reference counting. `Locations` is `Send` and `Sync`,
+/// so clones or references can be used for lookups from multiple threads.
+///
+/// ```
+/// use libloc::Locations;
+///
+/// fn assert_send_sync<T: Send + Sync>() {}
+/// assert_send_sync::<Locations>();
+///
+/// let locations = Locations::open("example-location.db")?;
+/// let clone = locations.clone();
+/// drop(locations);
+/// let thread = std::thread::spawn(move || {
+///     clone.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap().asn()
+/// });
+/// assert_eq!(thread.join().unwrap(), 204867);
+///
+/// # Ok::<(), libloc::OpenError>(())
+/// ```
+#[derive(Clone)]
+pub struct Locations {
+    inner: Yoke<LocationsInner<'static>, Arc<Data>>,
+}
+
+/// The bytes of a database.
+enum Data {
+    Mmap(Mmap),
+    Owned(Vec<u8>),
+}
+
+impl Deref for Data {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        match self {
+            Data::Mmap(mmap) => mmap,
+            Data::Owned(bytes) => bytes,
+        }
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "verified", derive(yoke_derive::Yokeable))]
+struct LocationsInner<'a> {
+    /// The bytes of the database, from the start of its header to the end of
+    /// its last section.
+    data: &'a [u8],
+    /// The bytes covered by the signatures of the database, from the start of
+    /// its header to the end of the file or the start of the next database.
+    signed_data: &'a [u8],
+    header: &'a format::Header,
+    as_: &'a [format::As],
+    networks: &'a [format::Network],
+    network_nodes: &'a [format::NetworkNode],
+    countries: &'a [format::Country],
+    string_pool: &'a [u8],
+    ipv4_network_node: Option<u32>,
+}
+
+#[cfg(not(feature = "verified"))]
+unsafe impl<'a> yoke::Yokeable<'a> for LocationsInner<'static> {
+    type Output = LocationsInner<'a>;
+    fn transform(&'a self) -> &'a LocationsInner<'a> {
+        self
+    }
+    fn transform_owned(self) -> LocationsInner<'a> {
+        self
+    }
+    unsafe fn make(from: LocationsInner<'a>) -> LocationsInner<'static> {
+        // We're just doing mem::transmute() here, however Rust is
+        // not smart enough to realize that Bar<'a> and Bar<'static> are of
+        // the same size, so instead we use transmute_copy
+        assert!(
+            std::mem::size_of::<LocationsInner<'a>>()
+                == std::mem::size_of::<LocationsInner<'static>>()
+        );
+        let ptr: *const LocationsInner<'static> = (&from as *const LocationsInner<'a>).cast();
+        #[allow(clippy::forget_non_drop)]
+        std::mem::forget(from);
+        std::ptr::read(ptr)
+    }
+    fn transform_mut<F: FnOnce(&'a mut LocationsInner<'a>) + 'static>(&'a mut self, f: F) {
+        unsafe {
+            f(std::mem::transmute::<
+                &mut LocationsInner<'static>,
+                &mut LocationsInner<'a>,
+            >(self))
+        }
+    }
+}
+
+impl<'a> LocationsInner<'a> {
+    fn find_network(&self, root: u32, bits_reverse: u128, num_bits: u32) -> Option<(u8, u32)> {
+        self.try_find_network(root, bits_reverse, num_bits)
+            .unwrap_or_else(|e| corrupt(e))
+    }
+    fn try_find_network(
+        &self,
+        root: u32,
+        bits_reverse: u128,
+        num_bits: u32,
+    ) -> Result<Option<(u8, u32)>, LookupError> {
+        let cursor = self.try_walk(Cursor::root(root), bits_reverse, num_bits)?;
+        cursor.unwrap_or_else(|c| c).try_network(self)
+    }
+    fn walk(&self, cursor: Cursor, bits_reverse: u128, num_bits: u32) -> Result<Cursor, Cursor> {
+        self.try_walk(cursor, bits_reverse, num_bits)
+            .unwrap_or_else(|e| corrupt(e))
+    }
+    fn try_walk(
+        &self,
+        cursor: Cursor,
+        bits_reverse: u128,
+        num_bits: u32,
+    ) -> Result<Result<Cursor, Cursor>, LookupError> {
+        // Walk the tree, remembering the last network we saw. Returns
+        // `Ok(Err(_))` if the tree ends before all bits have been consumed.
+        let mut bits = bits_reverse;
+        let mut cursor = cursor;
+        let mut cur = self.try_network_node(cursor.node)?;
+        for _ in 0..num_bits {
+            let next_index = cur.children[(bits & 1 != 0) as usize].get();
+            if next_index == 0 {
+                return Ok(Err(cursor));
+            }
+            cursor.last_network = cur
+                .network()
+                .map(|n| (cursor.used_bits, n))
+                .or(cursor.last_network);
+            bits >>= 1;
+            cursor.used_bits += 1;
+            cursor.node = next_index;
+            cur = self.try_network_node(next_index)?;
+        }
+        Ok(Ok(cursor))
+    }
+    /// The length of the database, from the start of its header to the end
+    /// of its last section.
+    fn len(&self) -> usize {
+        let header = self.header;
+        [
+            header.as_,
+            header.networks,
+            header.network_nodes,
+            header.countries,
+            header.string_pool,
+        ]
+        .iter()
+        .map(|range| range.offset.get() as usize + range.length.get() as usize)
+        .fold(std::mem::size_of::<format::Header>(), usize::max)
+    }
+    fn lookup(&self, addr: IpAddr) -> Option<Network<'a>> {
+        match addr {
+            IpAddr::V4(addr) => self.lookup_v4(addr).map(Into::into),
+            IpAddr::V6(addr) => self.lookup_v6(addr).map(Into::into),
+        }
+    }
+    fn lookup_v4(&self, addr: Ipv4Addr) -> Option<NetworkV4<'a>> {
+        let network = self.find_network(
+            self.ipv4_network_node?,
+            u32::from(addr).reverse_bits().into(),
+            32,
+        )?;
+        Some(self.network_v4(addr, network))
+    }
+    fn lookup_v6(&self, addr: Ipv6Addr) -> Option<NetworkV6<'a>> {
+        let network = self.find_network(0, u128::from(addr).reverse_bits(), 128)?;
+        Some(self.network_v6(addr, network))
+    }
+    fn try_lookup(&self, addr: IpAddr) -> Result<Option<Network<'a>>, LookupError> {
+        Ok(match addr {
+            IpAddr::V4(addr) => self.try_lookup_v4(addr)?.map(Into::into),
+            IpAddr::V6(addr) => self.try_lookup_v6(addr)?.map(Into::into),
+        })
+    }
+    fn try_lookup_v4(&self, addr: Ipv4Addr) -> Result<Option<NetworkV4<'a>>, LookupError> {
+        let root = match self.ipv4_network_node {
+            Some(root) => root,
+            None => return Ok(None),
+        };
+        let bits_reverse = u32::from(addr).reverse_bits().into();
+        let (num_bits, network_idx) = match self.try_find_network(root, bits_reverse, 32)? {
+            Some(network) => network,
+            None => return Ok(None),
+        };
+        Ok(Some(NetworkV4 {
+            inner: self.try_network_inner(network_idx)?,
+            addrs: Ipv4Net::new(addr, num_bits).unwrap().trunc(),
+        }))
+    }
+    fn try_lookup_v6(&self, addr: Ipv6Addr) -> Result<Option<NetworkV6<'a>>, LookupError> {
+        let bits_reverse = u128::from(addr).reverse_bits();
+        let (num_bits, network_idx) = match self.try_find_network(0, bits_reverse, 128)? {
+            Some(network) => network,
+            None => return Ok(None),
+        };
+        Ok(Some(NetworkV6 {
+            inner: self.try_network_inner(network_idx)?,
+            addrs: Ipv6Net::new(addr, num_bits).unwrap().trunc(),
+        }))
+    }
+    fn try_network_inner(&self, index: u32) -> Result<NetworkInner<'a>, LookupError> {
+        let network = self.try_network(index)?;
+        try_from_utf8(&network.country_code, "network country code")?;
+        Ok(NetworkInner::from(self, network))
+    }
+    fn try_as_by_asn(&self, asn: u32) -> Result<Option<As<'a>>, LookupError> {
+        let index = match self.find_as(asn) {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+        let as_ = self.try_as_(index)?;
+        let name = self.try_string_bytes(as_.name)?;
+        try_from_utf8(name, "AS name")?;
+        Ok(Some(As {
+            asn: as_.id.get(),
+            name: PoolStr::Valid(name),
+        }))
+    }
+    fn try_country_by_code(&self, code: &str) -> Result<Option<Country<'a>>, LookupError> {
+        let index = match self.find_country(code) {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+        let country = self.try_country(index)?;
+        let name = self.try_string_bytes(country.name)?;
+        try_from_utf8(&country.code, "country code")?;
+        try_from_utf8(&country.continent_code, "country continent code")?;
+        try_from_utf8(name, "country name")?;
+        Ok(Some(Country {
+            code: &country.code,
+            continent_code: &country.continent_code,
+            name: PoolStr::Valid(name),
+        }))
+    }
+    fn find_as(&self, asn: u32) -> Option<u32> {
+        // The ASs are stored sorted by ASN in the database, so we can use a
+        // binary search to find a particular one.
+        let index = self
+            .as_
+            .binary_search_by_key(&asn, |as_| as_.id.get())
+            .ok()?;
+        Some(index.try_into().unwrap())
+    }
+    /// Find a country by a code entered by a user, ignoring case and
+    /// surrounding whitespace.
+    fn find_country(&self, code: &str) -> Option<u32> {
+        self.find_country_code(&normalize_country_code(code)?)
+    }
+    fn find_country_code(&self, code: &[u8; 2]) -> Option<u32> {
+        // The countries are stored sorted by country code in the database, so
+        // we can use a binary search to find a particular one.
+        let index = self.countries.binary_search_by_key(code, |c| c.code).ok()?;
+        Some(index.try_into().unwrap())
+    }
+    fn as_of(&self, network: &NetworkInner<'a>) -> Option<As<'a>> {
+        if network.asn == 0 {
+            return None;
+        }
+        Some(As::from(self, self.as_(self.find_as(network.asn)?)))
+    }
+    fn country_of(&self, network: &NetworkInner<'a>) -> Option<Country<'a>> {
+        if network.country_code == b"XX" {
+            return None;
+        }
+        let index = self.find_country_code(network.country_code)?;
+        Some(Country::from(self, self.country(index)))
+    }
+    fn path_of(&self, addr: IpAddr) -> PathNetworks<'_, 'a> {
+        match addr {
+            IpAddr::V4(addr) => PathNetworks {
+                inner: self,
+                next: self.ipv4_network_node.map(|root| (root, 0)),
+                bits_reverse: u32::from(addr).reverse_bits().into(),
+                remaining_bits: 32,
+            },
+            IpAddr::V6(addr) => PathNetworks {
+                inner: self,
+                next: Some((0, 0)),
+                bits_reverse: u128::from(addr).reverse_bits(),
+                remaining_bits: 128,
+            },
+        }
+    }
+    fn network_at(&self, bits: u128, len: u8, network_idx: u32) -> Network<'a> {
+        Network {
+            inner: NetworkInner::from(self, self.network(network_idx)),
+            addrs: prefix(bits, len),
+        }
+    }
+    fn network_v4(&self, addr: Ipv4Addr, (num_bits, network_idx): (u8, u32)) -> NetworkV4<'a> {
+        NetworkV4 {
+            inner: NetworkInner::from(self, self.network(network_idx)),
+            addrs: Ipv4Net::new(addr, num_bits).unwrap().trunc(),
+        }
+    }
+    fn network_v6(&self, addr: Ipv6Addr, (num_bits, network_idx): (u8, u32)) -> NetworkV6<'a> {
+        NetworkV6 {
+            inner: NetworkInner::from(self, self.network(network_idx)),
+            addrs: Ipv6Net::new(addr, num_bits).unwrap().trunc(),
+        }
+    }
+    fn find_network_node(&self, root: u32, bits_reverse: u128, num_bits: u32) -> Option<u32> {
+        // Walk the tree.
+        let mut bits = bits_reverse;
+        let mut cur_index = root;
+        for _ in 0..num_bits {
+            cur_index = self.network_node(cur_index).children[(bits & 1 != 0) as usize].get();
+            if cur_index == 0 {
+                return None;
+            }
+            bits >>= 1;
+        }
+        Some(cur_index)
+    }
+    #[inline]
+    fn as_(&self, index: u32) -> &'a format::As {
+        self.try_as_(index).unwrap_or_else(|e| corrupt(e))
+    }
+    #[inline]
+    fn network(&self, index: u32) -> &'a format::Network {
+        self.try_network(index).unwrap_or_else(|e| corrupt(e))
+    }
+    #[inline]
+    fn network_node(&self, index: u32) -> &'a format::NetworkNode {
+        self.try_network_node(index).unwrap_or_else(|e| corrupt(e))
+    }
+    #[inline]
+    fn country(&self, index: u32) -> &'a format::Country {
+        self.try_country(index).unwrap_or_else(|e| corrupt(e))
+    }
+    #[inline]
+    fn try_as_(&self, index: u32) -> Result<&'a format::As, LookupError> {
+        record(self.as_, "as", index)
+    }
+    #[inline]
+    fn try_network(&self, index: u32) -> Result<&'a format::Network, LookupError> {
+        record(self.networks, "network", index)
+    }
+    #[inline]
+    fn try_network_node(&self, index: u32) -> Result<&'a format::NetworkNode, LookupError> {
+        record(self.network_nodes, "network node", index)
+    }
+    #[inline]
+    fn try_country(&self, index: u32) -> Result<&'a format::Country, LookupError> {
+        record(self.countries, "country", index)
+    }
+    fn string_bytes(&self, str_ref: format::StrRef) -> &'a [u8] {
+        self.try_string_bytes(str_ref)
+            .unwrap_or_else(|e| corrupt(e))
+    }
+    fn try_string_bytes(&self, str_ref: format::StrRef) -> Result<&'a [u8], LookupError> {
+        self.pool_str(str_ref).result()
+    }
+    fn pool_str(&self, str_ref: format::StrRef) -> PoolStr<'a> {
+        let offset = str_ref.offset.get();
+        // An offset equal to the length of the pool leaves no room for even
+        // the null termination, so it's as invalid as one past the end.
+        let bytes = match self.string_pool.get(offset as usize..) {
+            Some(bytes) if !bytes.is_empty() => bytes,
+            _ => return PoolStr::OutOfBounds(offset),
+        };
+        match bytes.iter().copied().position(|b| b == 0) {
+            Some(len) => PoolStr::Valid(&bytes[..len]),
+            None => PoolStr::Unterminated(offset, bytes),
+        }
+    }
+    fn string(&self, str_ref: format::StrRef) -> &'a str {
+        try_from_utf8(self.string_bytes(str_ref), "string pool").unwrap_or_else(|e| corrupt(e))
+    }
+    fn string_lossy(&self, str_ref: format::StrRef) -> Cow<'a, str> {
+        // Never panic: out-of-range references yield an empty string and a
+        // missing null termination ends the string at the end of the pool.
+        String::from_utf8_lossy(self.pool_str(str_ref).bytes_lossy())
+    }
+}
+
+/// The continent codes, see [`Country::continent_code`].
+const CONTINENT_CODES: [&str; 7] = ["AF", "AN", "AS", "EU", "NA", "OC", "SA"];
+
+/// A country code as stored in the database, ignoring case and surrounding
+/// whitespace. `None` if it doesn't consist of two ASCII letters.
+fn normalize_country_code(code: &str) -> Option<[u8; 2]> {
+    let code = code.trim().as_bytes();
+    if code.len() != 2 || !code.iter().all(u8::is_ascii_alphabetic) {
+        return None;
+    }
+    Some([code[0].to_ascii_uppercase(), code[1].to_ascii_uppercase()])
+}
+
+/// Depth-first walk over the networks of the network tree, yielding their
+/// bits, prefix lengths and network indices.
+struct TreeWalk<'a> {
+    inner: &'a LocationsInner<'a>,
+    /// Nodes still to visit, with their bits and depth.
+    stack: Vec<(u32, u128, u8)>,
+}
+
+impl<'a> TreeWalk<'a> {
+    fn new(inner: &'a LocationsInner<'a>) -> TreeWalk<'a> {
+        let mut stack = Vec::with_capacity(129);
+        if !inner.network_nodes.is_empty() {
+            stack.push((0, 0, 0));
+        }
+        TreeWalk { inner, stack }
+    }
+    /// Walk the subtree below the node at the given bits and depth.
+    fn from_node(inner: &'a LocationsInner<'a>, node: u32, bits: u128, depth: u8) -> TreeWalk<'a> {
+        let mut stack = Vec::with_capacity(129);
+        stack.push((node, bits, depth));
+        TreeWalk { inner, stack }
+    }
+    fn empty(inner: &'a LocationsInner<'a>) -> TreeWalk<'a> {
+        TreeWalk {
+            inner,
+            stack: Vec::new(),
+        }
+    }
+}
+
+impl<'a> Iterator for TreeWalk<'a> {
+    type Item = (u128, u8, u32);
+    fn next(&mut self) -> Option<(u128, u8, u32)> {
+        let (mut index, mut bits, mut depth) = self.stack.pop()?;
+        loop {
+            let node = self.inner.network_node(index);
+            let children = [node.children[0].get(), node.children[1].get()];
+            // Descend into the zero-child directly, avoiding the round-trip
+            // through the stack for the common chains of single children.
+            // Visiting the zero-child first yields the networks in address
+            // order.
+            let next = match children {
+                [0, 0] => self.stack.pop(),
+                [0, one] => Some((one, bits << 1 | 1, depth + 1)),
+                [zero, 0] => Some((zero, bits << 1, depth + 1)),
+                [zero, one] => {
+                    self.stack.push((one, bits << 1 | 1, depth + 1));
+                    Some((zero, bits << 1, depth + 1))
+                }
+            };
+            if let Some(network_idx) = node.network() {
+                if let Some(next) = next {
+                    self.stack.push(next);
+                }
+                return Some((bits, depth, network_idx));
+            }
+            (index, bits, depth) = next?;
+        }
+    }
+}
+
+/// Iterator over all networks of a database.
+///
+/// Returned by the [`Locations::networks`] function.
+pub struct Networks<'a> {
+    walk: TreeWalk<'a>,
+}
+
+impl<'a> Iterator for Networks<'a> {
+    type Item = Network<'a>;
+    fn next(&mut self) -> Option<Network<'a>> {
+        let (bits, len, network_idx) = self.walk.next()?;
+        Some(self.walk.inner.network_at(bits, len, network_idx))
+    }
+}
+
+/// Iterator over all ASes of a database.
+///
+/// Returned by the [`Locations::autonomous_systems`] function.
+pub struct AutonomousSystems<'a> {
+    inner: &'a LocationsInner<'a>,
+    iter: std::slice::Iter<'a, format::As>,
+}
+
+impl<'a> Iterator for AutonomousSystems<'a> {
+    type Item = As<'a>;
+    fn next(&mut self) -> Option<As<'a>> {
+        Some(As::from(self.inner, self.iter.next()?))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for AutonomousSystems<'a> {
+    fn next_back(&mut self) -> Option<As<'a>> {
+        Some(As::from(self.inner, self.iter.next_back()?))
+    }
+}
+
+impl<'a> ExactSizeIterator for AutonomousSystems<'a> {}
+
+/// Iterator over all countries of a database.
+///
+/// Returned by the [`Locations::countries`] function.
+pub struct Countries<'a> {
+    inner: &'a LocationsInner<'a>,
+    iter: std::slice::Iter<'a, format::Country>,
+}
+
+impl<'a> Iterator for Countries<'a> {
+    type Item = Country<'a>;
+    fn next(&mut self) -> Option<Country<'a>> {
+        Some(Country::from(self.inner, self.iter.next()?))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for Countries<'a> {
+    fn next_back(&mut self) -> Option<Country<'a>> {
+        Some(Country::from(self.inner, self.iter.next_back()?))
+    }
+}
+
+impl<'a> ExactSizeIterator for Countries<'a> {}
+
+/// A position in the network tree, reached by consuming `used_bits` bits.
+#[derive(Clone, Copy, Debug)]
+struct Cursor {
+    node: u32,
+    used_bits: u8,
+    /// Last network seen on the path, excluding the one at `node`.
+    last_network: Option<(u8, u32)>,
+}
+
+impl Cursor {
+    fn root(node: u32) -> Cursor {
+        Cursor {
+            node,
+            used_bits: 0,
+            last_network: None,
+        }
+    }
+    fn network(&self, inner: &LocationsInner) -> Option<(u8, u32)> {
+        self.try_network(inner).unwrap_or_else(|e| corrupt(e))
+    }
+    fn try_network(&self, inner: &LocationsInner) -> Result<Option<(u8, u32)>, LookupError> {
+        Ok(inner
+            .try_network_node(self.node)?
+            .network()
+            .map(|n| (self.used_bits, n))
+            .or(self.last_network))
+    }
+}
+
+/// The networks on the path of a tree walk, from the least specific one to
+/// the most specific one.
+struct PathNetworks<'i, 'a> {
+    inner: &'i LocationsInner<'a>,
+    /// The next node and the number of bits used to reach it.
+    next: Option<(u32, u8)>,
+    bits_reverse: u128,
+    remaining_bits: u32,
+}
+
+impl<'i, 'a> Iterator for PathNetworks<'i, 'a> {
+    type Item = (u8, u32);
+    fn next(&mut self) -> Option<(u8, u32)> {
+        loop {
+            let (index, used_bits) = self.next.take()?;
+            let node = self.inner.network_node(index);
+            if self.remaining_bits != 0 {
+                let next_index = node.children[(self.bits_reverse & 1 != 0) as usize].get();
+                if next_index != 0 {
+                    self.next = Some((next_index, used_bits + 1));
+                    self.bits_reverse >>= 1;
+                    self.remaining_bits -= 1;
+                }
+            }
+            if let Some(network) = node.network() {
+                return Some((used_bits, network));
+            }
+        }
+    }
+}
+
+trait ByteSliceExt {
+    fn get_range(&self, range: format::FileRange) -> Option<&[u8]>;
+    fn get_typed_range<T: FromBytes>(&self, range: format::FileRange) -> Option<&[T]>;
+}
+impl ByteSliceExt for [u8] {
+    fn get_range(&self, range: format::FileRange) -> Option<&[u8]> {
+        let start = range.offset.get();
+        let end = range.offset.get().checked_add(range.length.get())?;
+        self.get(start as usize..end as usize)
+    }
+    fn get_typed_range<T: FromBytes>(&self, range: format::FileRange) -> Option<&[T]> {
+        self.get_range(range).and_then(T::slice_from)
+    }
+}
+
+/// Check that the sections of the database are in bounds and consist of
+/// whole records.
+///
+/// Databases with an invalid magic, version or header are left for
+/// [`Locations::from_data`] to reject.
+#[cfg(feature = "verified")]
+fn check_layout(data: &[u8]) -> Result<(), OpenError> {
+    use zerocopy::Ref;
+
+    fn check<T: FromBytes + zerocopy::Unaligned>(
+        data: &[u8],
+        section: &'static str,
+        range: format::FileRange,
+    ) -> Result<(), OpenError> {
+        let valid = data
+            .get_range(range)
+            .and_then(Ref::<_, [T]>::new_slice_unaligned)
+            .is_some();
+        if !valid {
+            return Err(OpenError::InvalidSectionLayout {
+                section,
+                offset: range.offset.get(),
+                length: range.length.get(),
+                record_size: std::mem::size_of::<T>(),
+            });
+        }
+        Ok(())
+    }
+
+    let header = match Ref::<_, format::Header>::new_unaligned_from_prefix(data) {
+        Some((header, _)) if header.magic == format::MAGIC => header,
+        _ => return Ok(()),
+    };
+    if header.version != format::VERSION {
+        return Ok(());
+    }
+    check::<format::As>(data, "as", header.as_)?;
+    check::<format::Network>(data, "networks", header.networks)?;
+    check::<format::NetworkNode>(data, "network_nodes", header.network_nodes)?;
+    check::<format::Country>(data, "countries", header.countries)?;
+    check::<u8>(data, "string_pool", header.string_pool)?;
+    Ok(())
+}
+
+impl Locations {
+    /// Open a database in libloc format.
+    ///
+    /// # Safety
+    ///
+    /// This memory-maps the database. This is efficient, but you must make
+    /// sure that it's not modified during the usage. See the safety discussion
+    /// of the `Mmap` struct of [`memmap2`](https://docs.rs/memmap2/).
+    ///
+    /// # Errors
+    ///
+    /// Errors can occur when the specified database file cannot be opened for
+    /// reading (e.g. because it does not exist), this is communicated via the
+    /// [`OpenError::Open`] variant.
+    ///
+    /// Additionally, if the opened file is not in a format valid for this
+    /// crate, it is likely that the [`OpenError::InvalidMagic`] variant is
+    /// returned.
+    ///
+    /// If the database is obviously corrupt, e.g. truncated, other errors
+    /// might be returned.
+    ///
+    /// # Environment
+    ///
+    /// On Unix, the kernel is advised to expect random accesses to the
+    /// memory-mapped database, which suits point lookups. This can be
+    /// overridden by setting the `LIBLOC_MMAP_ADVICE` environment variable to
+    /// one of `random`, `sequential`, `normal` or `willneed`, e.g. to
+    /// `sequential` for exporting whole databases. Unknown values are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    ///
+    /// // IO errors while opening the file are reported via the `Open(_)`
+    /// // variant.
+    /// assert!(matches!(Locations::open("non-existing"), Err(libloc::OpenError::Open(_))));
+    ///
+    /// // Files that are not in the required format are likely to give the
+    /// // `InvalidMagic` error.
+    /// assert!(matches!(Locations::open("Cargo.toml"), Err(libloc::OpenError::InvalidMagic)));
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Locations, OpenError> {
+        Locations::from_data(Locations::map(path.as_ref())?, 0)
+    }
+    /// Read a database in libloc format from an already opened file.
+    ///
+    /// This works for files without a path as well, e.g. a `memfd` received
+    /// from another process. The file is only mapped for reading and doesn't
+    /// need to stay open.
+    ///
+    /// # Safety
+    ///
+    /// See [`Locations::open`]. For a `memfd`, sealing it against writes
+    /// (`F_SEAL_WRITE`) guarantees that it's not modified.
+    ///
+    /// # Errors
+    ///
+    /// See [`Locations::open`], except that [`OpenError::Open`] is never
+    /// returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let file = std::fs::File::open("example-location.db").unwrap();
+    /// let locations = Locations::from_file(&file)?;
+    /// assert_eq!(locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap().asn(), 204867);
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    ///
+    /// Reading a database from a sealed `memfd` on Linux:
+    ///
+    /// ```
+    /// # #[cfg(target_os = "linux")]
+    /// # {
+    /// use libloc::Locations;
+    /// use std::fs::File;
+    /// use std::io::Write;
+    /// use std::os::unix::io::FromRawFd;
+    ///
+    /// let fd = unsafe { libc::memfd_create(b"libloc\0".as_ptr().cast(), libc::MFD_ALLOW_SEALING) };
+    /// assert!(fd >= 0);
+    /// let mut file = unsafe { File::from_raw_fd(fd) };
+    /// file.write_all(&std::fs::read("example-location.db").unwrap()).unwrap();
+    /// let seals = libc::F_SEAL_SHRINK | libc::F_SEAL_GROW | libc::F_SEAL_WRITE | libc::F_SEAL_SEAL;
+    /// assert_eq!(unsafe { libc::fcntl(fd, libc::F_ADD_SEALS, seals) }, 0);
+    ///
+    /// let locations = Locations::from_file(&file)?;
+    /// drop(file);
+    /// assert_eq!(locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap().asn(), 204867);
+    /// # }
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn from_file(file: &File) -> Result<Locations, OpenError> {
+        Locations::from_data(Locations::map_file(file)?, 0)
+    }
+    /// Read a database in libloc format from memory.
+    ///
+    /// Unlike [`Locations::open`], this doesn't memory-map a file and thus
+    /// has no safety caveat.
+    ///
+    /// # Errors
+    ///
+    /// See [`Locations::open`], except that [`OpenError::Open`] and
+    /// [`OpenError::Mmap`] are never returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let bytes = std::fs::read("example-location.db").unwrap();
+    /// let locations = Locations::from_bytes(bytes)?;
+    /// let mapped = Locations::open("example-location.db")?;
+    ///
+    /// let addr = "2a07:1c44:5800::1".parse().unwrap();
+    /// let network = locations.lookup(addr).unwrap();
+    /// let mapped_network = mapped.lookup(addr).unwrap();
+    /// assert_eq!(network.addrs(), mapped_network.addrs());
+    /// assert_eq!(network.asn(), mapped_network.asn());
+    /// assert_eq!(network.country_code(), mapped_network.country_code());
+    /// assert_eq!(locations.vendor(), mapped.vendor());
+    ///
+    /// assert!(matches!(Locations::from_bytes(*b"LOCDBXX"), Err(libloc::OpenError::CouldntReadHeader)));
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn from_bytes(data: impl Into<Vec<u8>>) -> Result<Locations, OpenError> {
+        Locations::from_data(Arc::new(Data::Owned(data.into())), 0)
+    }
+    /// Read a database in libloc format from a reader.
+    ///
+    /// The whole database is read into memory, see [`Locations::from_bytes`].
+    /// This allows reading databases from e.g. decompressors or network
+    /// streams.
+    ///
+    /// # Errors
+    ///
+    /// Errors from the reader are returned via the [`OpenError::Read`]
+    /// variant. Otherwise, see [`Locations::from_bytes`]. Truncated databases
+    /// lead to errors like [`OpenError::CouldntReadHeader`] or
+    /// [`OpenError::InvalidStringPoolRange`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libloc::{Locations, OpenError};
+    /// use std::io::Cursor;
+    ///
+    /// let bytes = std::fs::read("example-location.db").unwrap();
+    /// let locations = Locations::from_reader(Cursor::new(&bytes))?;
+    /// assert_eq!(locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap().asn(), 204867);
+    ///
+    /// let truncated = Locations::from_reader(Cursor::new(&bytes[..100]));
+    /// assert!(matches!(truncated, Err(OpenError::CouldntReadHeader)));
+    /// let truncated = Locations::from_reader(Cursor::new(&bytes[..bytes.len() - 1]));
+    /// assert!(matches!(truncated, Err(OpenError::InvalidStringPoolRange)));
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn from_reader<R: Read>(reader: R) -> Result<Locations, OpenError> {
+        let mut reader = reader;
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).map_err(OpenError::Read)?;
+        Locations::from_bytes(data)
+    }
+    /// Open a database in libloc format, reading it into memory.
+    ///
+    /// Unlike [`Locations::open`], this doesn't memory-map the file and thus
+    /// has no safety caveat, at the cost of holding the whole database in
+    /// memory. This can also be faster on network file systems.
+    ///
+    /// # Errors
+    ///
+    /// See [`Locations::open`], except that [`OpenError::Mmap`] is never
+    /// returned. Errors reading the file are returned via the
+    /// [`OpenError::Open`] variant.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libloc::{Backing, Locations};
+    ///
+    /// let in_memory = Locations::open_in_memory("example-location.db")?;
+    /// let mapped = Locations::open("example-location.db")?;
+    /// assert_eq!(in_memory.backing(), Backing::OwnedBytes);
+    ///
+    /// let addr = "2a07:1c44:5800::1".parse().unwrap();
+    /// assert_eq!(in_memory.lookup(addr), mapped.lookup(addr));
+    /// let (mut a, mut b) = (Vec::new(), Vec::new());
+    /// in_memory.to_jsonl(&mut a).unwrap();
+    /// mapped.to_jsonl(&mut b).unwrap();
+    /// assert_eq!(a, b);
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn open_in_memory<P: AsRef<Path>>(path: P) -> Result<Locations, OpenError> {
+        Locations::from_bytes(std::fs::read(path).map_err(OpenError::Open)?)
+    }
+    /// Open a database in libloc format, checking the layout of each section
+    /// precisely.
+    ///
+    /// In addition to the checks of [`Locations::open`], this verifies with
+    /// zerocopy's checked parsing that every section is in bounds and made
+    /// of whole records. Instead of a generic range error, a mismatch is
+    /// reported as [`OpenError::InvalidSectionLayout`], naming the section
+    /// and its size.
+    ///
+    /// # Safety
+    ///
+    /// See [`Locations::open`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libloc::{Locations, OpenError};
+    ///
+    /// let locations = Locations::open_checked("example-location.db")?;
+    ///
+    /// // Truncate the `network_nodes` section by one byte.
+    /// let mut database = std::fs::read("example-location.db").unwrap();
+    /// database[51] -= 1;
+    /// let path = std::env::temp_dir().join("libloc-doctest-open-checked.db");
+    /// std::fs::write(&path, database).unwrap();
+    /// assert!(matches!(
+    ///     Locations::open_checked(&path),
+    ///     Err(OpenError::InvalidSectionLayout { section: "network_nodes", record_size: 12, .. }),
+    /// ));
+    /// # std::fs::remove_file(&path).unwrap();
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    #[cfg(feature = "verified")]
+    pub fn open_checked<P: AsRef<Path>>(path: P) -> Result<Locations, OpenError> {
+        let data = Locations::map(path.as_ref())?;
+        check_layout(&data)?;
+        Locations::from_data(data, 0)
+    }
+    /// Open a file containing multiple concatenated databases in libloc
+    /// format.
+    ///
+    /// Each database starts right after the end of the last section of the
+    /// previous one, optionally padded with zero bytes. They share a single
+    /// memory mapping.
+    ///
+    /// Reading stops at the first bytes after a database that are neither
+    /// padding nor the magic bytes of another database. Like for
+    /// [`Locations::open`], such trailing data is ignored.
+    ///
+    /// # Safety
+    ///
+    /// See [`Locations::open`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Locations::open`]. An error is returned if any of the databases
+    /// is invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let database = std::fs::read("example-location.db").unwrap();
+    /// let path = std::env::temp_dir().join("libloc-doctest-open-all.db");
+    /// std::fs::write(&path, [&database[..], &database[..]].concat()).unwrap();
+    ///
+    /// let all = Locations::open_all(&path)?;
+    /// assert_eq!(all.len(), 2);
+    /// for locations in &all {
+    ///     assert_eq!(locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap().asn(), 204867);
+    /// }
+    ///
+    /// // Padding is skipped, trailing data isn't searched for databases.
+    /// let file = [&database[..], &[0; 4096], &database[..], b"garbage", &database[..]].concat();
+    /// std::fs::write(&path, file).unwrap();
+    /// assert_eq!(Locations::open_all(&path)?.len(), 2);
+    /// # std::fs::remove_file(&path).unwrap();
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn open_all<P: AsRef<Path>>(path: P) -> Result<Vec<Locations>, OpenError> {
+        fn inner(path: &Path) -> Result<Vec<Locations>, OpenError> {
+            let data = Locations::map(path)?;
+            let mut result: Vec<Locations> = Vec::new();
+            let mut start = 0;
+            loop {
+                let locations = Locations::from_data(data.clone(), start)?;
+                start += locations.inner.get().len();
+                if let Some(previous) = result.last_mut() {
+                    // Only the last database has its signatures cover the
+                    // bytes after it.
+                    previous
+                        .inner
+                        .with_mut(|inner| inner.signed_data = inner.data);
+                }
+                result.push(locations);
+                // Skip potential padding between the databases.
+                start += data[start..].iter().take_while(|&&b| b == 0).count();
+                if !data[start..].starts_with(&format::MAGIC) {
+                    break;
+                }
+            }
+            Ok(result)
+        }
+        inner(path.as_ref())
+    }
+    /// Read the header of a database in libloc format.
+    ///
+    /// Unlike [`Locations::open`], this only checks the magic, the version and
+    /// that the header is complete, so it can be used to inspect databases
+    /// with corrupt data sections.
+    ///
+    /// # Safety
+    ///
+    /// See [`Locations::open`].
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let header = Locations::header_info("example-location.db")?;
+    /// assert_eq!(header.created_at, 1707258629);
+    /// assert_eq!(header.vendor.as_deref(), Some("IPFire Project"));
+    /// assert_eq!(header.sections[2].name, "network_nodes");
+    /// assert_eq!(header.sections[2].length, 41 * 12);
+    /// assert!(header.is_signed());
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn header_info<P: AsRef<Path>>(path: P) -> Result<HeaderInfo, OpenError> {
+        use self::OpenError as Error;
+        let data = Locations::map(path.as_ref())?;
+        if !data.starts_with(&format::MAGIC) {
+            return Err(Error::InvalidMagic);
+        }
+        let header = format::Header::ref_from_prefix(&data).ok_or(Error::CouldntReadHeader)?;
+        if header.version != format::VERSION {
+            return Err(Error::UnsupportedVersion(header.version));
+        }
+        let string_pool = data.get_range(header.string_pool);
+        let string = |str_ref: format::StrRef| {
+            let bytes = string_pool?.get(str_ref.offset.get() as usize..)?;
+            let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            Some(String::from_utf8_lossy(&bytes[..len]).into_owned())
+        };
+        let section = |name, range: format::FileRange| SectionInfo {
+            name,
+            offset: range.offset.get(),
+            length: range.length.get(),
+        };
+        Ok(HeaderInfo {
+            version: header.version,
+            created_at: header.created_at.get(),
+            vendor: string(header.vendor),
+            description: string(header.description),
+            license: string(header.license),
+            sections: vec![
+                section("as", header.as_),
+                section("networks", header.networks),
+                section("network_nodes", header.network_nodes),
+                section("countries", header.countries),
+                section("string_pool", header.string_pool),
+            ],
+            signature1_length: header.signature1_length.get(),
+            signature2_length: header.signature2_length.get(),
+            file_size: data.len(),
+        })
+    }
+    fn map(path: &Path) -> Result<Arc<Data>, OpenError> {
+        Locations::map_file(&File::open(path).map_err(OpenError::Open)?)
+    }
+    fn map_file(file: &File) -> Result<Arc<Data>, OpenError> {
+        let mmap = unsafe { Mmap::map(file) }.map_err(OpenError::Mmap)?;
+
+        // This is just an optimization, ignore errors.
+        #[cfg(unix)]
+        let _ = mmap.advise(mmap_advice());
+
+        Ok(Arc::new(Data::Mmap(mmap)))
+    }
+    fn from_data(data: Arc<Data>, start: usize) -> Result<Locations, OpenError> {
+        use self::OpenError as Error;
+
+        if !data[start..].starts_with(&format::MAGIC) {
+            return Err(Error::InvalidMagic);
+        }
+
+        let inner = Yoke::try_attach_to_cart(data, |data| -> Result<_, Error> {
+            let data = &data[start..];
+            let header = format::Header::ref_from_prefix(data).ok_or(Error::CouldntReadHeader)?;
+            if header.version != format::VERSION {
+                return Err(Error::UnsupportedVersion(header.version));
+            }
+
+            let mut inner = LocationsInner {
+                as_: data
+                    .get_typed_range(header.as_)
+                    .ok_or(Error::InvalidAsRange)?,
+                networks: data
+                    .get_typed_range(header.networks)
+                    .ok_or(Error::InvalidNetworkRange)?,
+                network_nodes: data
+                    .get_typed_range(header.network_nodes)
+                    .ok_or(Error::InvalidNetworkNodeRange)?,
+                countries: data
+                    .get_typed_range(header.countries)
+                    .ok_or(Error::InvalidCountryRange)?,
+                string_pool: data
+                    .get_range(header.string_pool)
+                    .ok_or(Error::InvalidStringPoolRange)?,
+
+                header,
+                data,
+                signed_data: data,
+
+                ipv4_network_node: Some(u32::MAX), // invalid value
+            };
+            inner.data = &data[..inner.len()];
+            let ipv4_mapped_prefix = u128::from(Ipv4Addr::from(0).to_ipv6_mapped());
+            inner.ipv4_network_node =
+                inner.find_network_node(0, ipv4_mapped_prefix.reverse_bits(), 96);
+            Ok(inner)
+        })?;
+        Ok(Locations { inner })
+    }
+    /// What the bytes of the database are stored in.
+    ///
+    /// Only databases backed by [`Backing::MmapFile`] are subject to the
+    /// safety caveat of [`Locations::open`].
+    ///
+    /// ```
+    /// use libloc::{Backing, Locations, TestDatabaseBuilder};
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// assert_eq!(locations.backing(), Backing::MmapFile);
+    /// assert_eq!(TestDatabaseBuilder::new().build().backing(), Backing::OwnedBytes);
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn backing(&self) -> Backing {
+        match **self.inner.backing_cart() {
+            Data::Mmap(_) => Backing::MmapFile,
+            Data::Owned(_) => Backing::OwnedBytes,
+        }
+    }
+    /// The database creation time.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// assert_eq!(locations.created_at().to_string(), "2024-02-06 22:30:29 UTC");
+    /// assert_eq!(
+    ///     std::time::SystemTime::from(locations.created_at()),
+    ///     locations.created_at_system_time(),
+    /// );
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    #[cfg(feature = "time")]
+    pub fn created_at(&self) -> chrono::DateTime<chrono::offset::Utc> {
+        let inner = self.inner.get();
+        timestamp(inner.header.created_at.get(), "created_at header")
+    }
+    /// The database creation time as stored in the header.
+    ///
+    /// This is the number of seconds since the Unix epoch, without any
+    /// validation. Unlike [`Locations::created_at`], this never panics.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// assert_eq!(locations.created_at_raw(), 1707258629);
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn created_at_raw(&self) -> u64 {
+        self.inner.get().header.created_at.get()
+    }
+    /// The database creation time as a [`SystemTime`].
+    ///
+    /// Unlike [`Locations::created_at`], this is available without the `time`
+    /// feature and its `chrono` dependency.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    /// use std::time::{Duration, UNIX_EPOCH};
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// assert_eq!(
+    ///     locations.created_at_system_time(),
+    ///     UNIX_EPOCH + Duration::from_secs(1707258629),
+    /// );
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn created_at_system_time(&self) -> SystemTime {
+        let secs = self.created_at_raw();
+        UNIX_EPOCH
+            .checked_add(Duration::from_secs(secs))
+            .unwrap_or_else(|| panic!("corrupt libloc db: invalid created_at header: {}", secs))
+    }
+    /// The time the most recently changed network was updated.
+    ///
+    /// The database format does not store per-network timestamps yet, so this
+    /// is currently the same as [`Locations::created_at`]. See also
+    /// [`Network::updated_at`].
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// assert_eq!(locations.newest_network_age(), locations.created_at());
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    #[cfg(feature = "time")]
+    pub fn newest_network_age(&self) -> chrono::DateTime<chrono::offset::Utc> {
+        self.created_at()
+    }
+    /// The format version of the database.
+    ///
+    /// This is the version byte from the database header, currently always
+    /// [`SUPPORTED_VERSION`].
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// assert_eq!(locations.format_version(), libloc::SUPPORTED_VERSION);
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn format_version(&self) -> u8 {
+        self.inner.get().header.version
+    }
+    /// An identifier of the database build, for logging and audit trails.
+    ///
+    /// It has the form `<vendor>-v<version>-<created_at>-<hash>`, e.g.
+    /// `ipfire-project-v1-20240206223029-1a2b3c4d5e6f7081`. The vendor is
+    /// lowercased with runs of other characters than ASCII letters and
+    /// digits replaced by `-`, the creation time is in UTC and the hash is a
+    /// 64-bit hash of the data sections in hexadecimal.
+    ///
+    /// Identical databases have the same identifier, changes to the data
+    /// sections, the vendor, the format version or the creation time change
+    /// it. The hash is not cryptographic, use the database signatures to
+    /// detect deliberate modifications.
+    ///
+    /// The identifier is not cached: every call hashes all data sections,
+    /// which takes time proportional to the database size. Call it once and
+    /// keep the result if it's needed repeatedly.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// assert_eq!(locations.build_id(), "ipfire-project-v1-20240206223029-22e774619d233ff7");
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn build_id(&self) -> String {
+        let inner = self.inner.get();
+        let mut vendor = String::new();
+        for c in inner.string_lossy(inner.header.vendor).chars() {
+            if c.is_ascii_alphanumeric() {
+                vendor.push(c.to_ascii_lowercase());
+            } else if !vendor.is_empty() && !vendor.ends_with('-') {
+                vendor.push('-');
+            }
+        }
+        if vendor.ends_with('-') {
+            vendor.pop();
+        }
+        if vendor.is_empty() {
+            vendor.push_str("unknown");
+        }
+        let hash = fnv1a(&[
+            inner.as_.as_bytes(),
+            inner.networks.as_bytes(),
+            inner.network_nodes.as_bytes(),
+            inner.countries.as_bytes(),
+            inner.string_pool,
+        ]);
+        format!(
+            "{}-v{}-{}-{:016x}",
+            vendor,
+            inner.header.version,
+            compact_timestamp(inner.header.created_at.get()),
+            hash,
+        )
+    }
+    /// Verify the signatures of the database against a public key.
+    ///
+    /// `public_key` is an ECDSA P-256 public key in PEM format, like the ones
+    /// upstream libloc signs its databases with. The signed data is the
+    /// database with the signature fields of the header zeroed, hashed with
+    /// SHA-256. The database is valid if either of its two signatures
+    /// matches.
+    ///
+    /// Like upstream libloc, this hashes the whole file, including any bytes
+    /// after the last section. For databases returned by
+    /// [`Locations::open_all`], only the bytes up to the start of the next
+    /// database are hashed.
+    ///
+    /// ```
+    /// use libloc::{Locations, SignatureError};
+    /// use p256::ecdsa::{signature::Signer, Signature, SigningKey};
+    /// use p256::pkcs8::{EncodePublicKey, LineEnding};
+    ///
+    /// // Sign the example database with a key of our own.
+    /// let key = SigningKey::from_bytes(&[1; 32].into()).unwrap();
+    /// let mut database = std::fs::read("example-location.db").unwrap();
+    /// database[68..4168].fill(0);
+    /// let signature: Signature = key.sign(&database);
+    /// let signature = signature.to_der();
+    /// database[68..70].copy_from_slice(&(signature.len() as u16).to_be_bytes());
+    /// database[72..72 + signature.len()].copy_from_slice(signature.as_bytes());
+    /// let path = std::env::temp_dir().join("libloc-doctest-verify-signature.db");
+    /// std::fs::write(&path, &database).unwrap();
+    ///
+    /// let public_key = key.verifying_key().to_public_key_pem(LineEnding::LF).unwrap();
+    /// let locations = Locations::open(&path)?;
+    /// assert!(locations.verify_signature(public_key.as_bytes()).is_ok());
+    ///
+    /// // Trailing bytes are covered by the signature, too.
+    /// let mut trailing = database.clone();
+    /// trailing.push(0);
+    /// assert!(matches!(
+    ///     Locations::from_bytes(trailing)?.verify_signature(public_key.as_bytes()),
+    ///     Err(SignatureError::VerificationFailed),
+    /// ));
+    /// assert!(matches!(
+    ///     locations.verify_signature(b"garbage"),
+    ///     Err(SignatureError::BadKey),
+    /// ));
+    ///
+    /// let other_key = SigningKey::from_bytes(&[2; 32].into()).unwrap();
+    /// let other_public_key = other_key.verifying_key().to_public_key_pem(LineEnding::LF).unwrap();
+    /// assert!(matches!(
+    ///     locations.verify_signature(other_public_key.as_bytes()),
+    ///     Err(SignatureError::VerificationFailed),
+    /// ));
+    /// # std::fs::remove_file(&path).unwrap();
+    ///
+    /// // Each of several concatenated databases is verified on its own.
+    /// let path = std::env::temp_dir().join("libloc-doctest-verify-signature-all.db");
+    /// std::fs::write(&path, [&database[..], &database[..]].concat()).unwrap();
+    /// for locations in Locations::open_all(&path)? {
+    ///     assert!(locations.verify_signature(public_key.as_bytes()).is_ok());
+    /// }
+    /// # std::fs::remove_file(&path).unwrap();
+    ///
+    /// let unsigned = libloc::TestDatabaseBuilder::new().build();
+    /// assert!(matches!(
+    ///     unsigned.verify_signature(public_key.as_bytes()),
+    ///     Err(SignatureError::NoSignature),
+    /// ));
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    #[cfg(feature = "signatures")]
+    pub fn verify_signature(&self, public_key: &[u8]) -> Result<(), SignatureError> {
+        use p256::ecdsa::signature::DigestVerifier;
+        use p256::ecdsa::{Signature, VerifyingKey};
+        use p256::pkcs8::DecodePublicKey;
+        use sha2::{Digest, Sha256};
+
+        let inner = self.inner.get();
+        let header = inner.header;
+        let signatures: Vec<&[u8]> = [
+            (header.signature1_length.get(), &header.signature1_buf),
+            (header.signature2_length.get(), &header.signature2_buf),
+        ]
+        .iter()
+        .filter(|&&(length, _)| length != 0)
+        .map(|&(length, buf)| buf.get(..usize::from(length)).unwrap_or(buf))
+        .collect();
+        if signatures.is_empty() {
+            return Err(SignatureError::NoSignature);
+        }
+        let key = str::from_utf8(public_key)
+            .ok()
+            .and_then(|pem| VerifyingKey::from_public_key_pem(pem).ok())
+            .ok_or(SignatureError::BadKey)?;
+
+        // The header with the signature fields zeroed, followed by the rest of
+        // the database.
+        let data = inner.signed_data;
+        let signatures_start =
+            header.signature1_length.as_bytes().as_ptr() as usize - data.as_ptr() as usize;
+        let signatures_end = header.padding.as_ptr() as usize - data.as_ptr() as usize;
+        let digest = Sha256::new()
+            .chain_update(&data[..signatures_start])
+            .chain_update(vec![0; signatures_end - signatures_start])
+            .chain_update(&data[signatures_end..]);
+        let valid = signatures.iter().any(|signature| {
+            Signature::from_der(signature)
+                .map(|signature| key.verify_digest(digest.clone(), &signature).is_ok())
+                .unwrap_or(false)
+        });
+        if !valid {
+            return Err(SignatureError::VerificationFailed);
+        }
+        Ok(())
+    }
+    /// The vendor of the database.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// assert_eq!(locations.vendor(), "IPFire Project");
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn vendor(&self) -> &str {
+        let inner = self.inner.get();
+        inner.string(inner.header.vendor)
+    }
+    /// The vendor of the database, replacing invalid UTF-8.
+    ///
+    /// Unlike [`Locations::vendor`], this never panics, even for a corrupt
+    /// database, see [`String::from_utf8_lossy`].
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// assert_eq!(locations.vendor_lossy(), "IPFire Project");
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn vendor_lossy(&self) -> Cow<'_, str> {
+        let inner = self.inner.get();
+        inner.string_lossy(inner.header.vendor)
+    }
+    /// The description of the database.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// assert_eq!(locations.description(), "This is a geo location database");
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn description(&self) -> &str {
+        let inner = self.inner.get();
+        inner.string(inner.header.description)
+    }
+    /// The description of the database, replacing invalid UTF-8.
+    ///
+    /// Unlike [`Locations::description`], this never panics, even for a corrupt
+    /// database, see [`String::from_utf8_lossy`].
+    pub fn description_lossy(&self) -> Cow<'_, str> {
+        let inner = self.inner.get();
+        inner.string_lossy(inner.header.description)
+    }
+    /// The license of the database.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// assert_eq!(locations.license(), "CC");
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn license(&self) -> &str {
+        let inner = self.inner.get();
+        inner.string(inner.header.license)
+    }
+    /// The license of the database, replacing invalid UTF-8.
+    ///
+    /// Unlike [`Locations::license`], this never panics, even for a corrupt
+    /// database, see [`String::from_utf8_lossy`].
+    pub fn license_lossy(&self) -> Cow<'_, str> {
+        let inner = self.inner.get();
+        inner.string_lossy(inner.header.license)
+    }
+    /// The vendor, description, license and creation time of the database.
+    ///
+    /// With the `serde` feature, [`Metadata`] can be serialized, with the
+    /// creation time as seconds since the Unix epoch.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let metadata = locations.metadata();
+    /// assert_eq!(metadata.vendor, locations.vendor());
+    /// assert_eq!(metadata.description, "This is a geo location database");
+    /// assert_eq!(metadata.license, locations.license());
+    /// assert_eq!(metadata.created_at, locations.created_at_system_time());
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn metadata(&self) -> Metadata {
+        Metadata {
+            vendor: self.vendor_lossy().into_owned(),
+            description: self.description_lossy().into_owned(),
+            license: self.license_lossy().into_owned(),
+            created_at: self.created_at_system_time(),
+        }
+    }
+    /// Look up an [AS] (autonomous system) by its [ASN] (number).
+    ///
+    /// Returns `None` if it does not appear in the database.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// assert_eq!(locations.as_(204867).unwrap().name(), "Lightning Wire Labs GmbH");
+    /// assert!(matches!(locations.as_(0), None));
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    ///
+    /// [AS]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
+    /// [ASN]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
+    pub fn as_(&self, asn: u32) -> Option<As<'_>> {
+        let inner = self.inner.get();
+        Some(As::from(inner, inner.as_(inner.find_as(asn)?)))
+    }
+    /// Look up an [AS] (autonomous system) by its [ASN] (number), returning
+    /// an error instead of panicking if the database is corrupt.
+    ///
+    /// Like [`Locations::as_`], this returns `Ok(None)` if there is no such
+    /// AS. The name of the returned AS is checked to be in bounds of the
+    /// string pool, null-terminated and valid UTF-8. A name pointing exactly
+    /// at the end of the string pool is out of bounds, as there is no room
+    /// for its null termination.
+    ///
+    /// ```
+    /// use libloc::{Locations, LookupError};
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let as_ = locations.try_as_(204867).unwrap().unwrap();
+    /// assert_eq!(as_.name(), "Lightning Wire Labs GmbH");
+    /// assert!(matches!(locations.try_as_(0), Ok(None)));
+    ///
+    /// let mut data = std::fs::read("example-location.db").unwrap();
+    /// // Point the AS name exactly at the end of the string pool.
+    /// let as_ = u32::from_be_bytes(data[28..32].try_into().unwrap()) as usize;
+    /// let pool_len = u32::from_be_bytes(data[64..68].try_into().unwrap());
+    /// data[as_ + 4..as_ + 8].copy_from_slice(&pool_len.to_be_bytes());
+    /// let locations = Locations::from_bytes(data)?;
+    /// assert!(matches!(
+    ///     locations.try_as_(204867),
+    ///     Err(LookupError::InvalidStrRef(offset)) if offset == pool_len,
+    /// ));
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    ///
+    /// [AS]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
+    /// [ASN]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
+    pub fn try_as_(&self, asn: u32) -> Result<Option<As<'_>>, LookupError> {
+        self.inner.get().try_as_by_asn(asn)
+    }
+    /// Whether an [AS] (autonomous system) with the given [ASN] (number) is
+    /// in the database.
+    ///
+    /// This is cheaper than checking the result of [`Locations::as_`], as it
+    /// doesn't resolve the AS's name.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// assert!(locations.has_asn(204867));
+    /// assert!(!locations.has_asn(0));
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    ///
+    /// [AS]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
+    /// [ASN]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
+    pub fn has_asn(&self, asn: u32) -> bool {
+        self.inner.get().find_as(asn).is_some()
+    }
+    /// The fraction of [AS]es (autonomous systems) in the database that have
+    /// at least one network.
+    ///
+    /// This walks the whole network tree. Returns 0 if there are no ASes in
+    /// the database.
+    ///
+    /// ```
+    /// use libloc::TestDatabaseBuilder;
+    ///
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network("192.0.2.0/24", 64496, "DE")
+    ///     .add_network("198.51.100.0/24", 64499, "DE")
+    ///     .add_as(64496, "Example")
+    ///     .add_as(64497, "Unrouted")
+    ///     .build();
+    ///
+    /// assert_eq!(locations.as_table_utilization(), 0.5);
+    /// ```
+    ///
+    /// [AS]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
+    pub fn as_table_utilization(&self) -> f64 {
+        let inner = self.inner.get();
+        if inner.as_.is_empty() {
+            return 0.0;
+        }
+        let routed: HashSet<u32> = TreeWalk::new(inner)
+            .map(|(_, _, network_idx)| inner.network(network_idx).asn.get())
+            .collect();
+        let utilized = inner
+            .as_
+            .iter()
+            .filter(|as_| routed.contains(&as_.id.get()))
+            .count();
+        utilized as f64 / inner.as_.len() as f64
+    }
+    /// Iterate over all [AS]es (autonomous systems) in the database.
+    ///
+    /// The ASes are ordered by their ASN.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let ases = locations.autonomous_systems();
+    /// assert_eq!(ases.len(), 1);
+    /// let asns: Vec<u32> = ases.map(|as_| as_.asn()).collect();
+    /// assert_eq!(asns, [204867]);
+    ///
+    /// let locations = libloc::TestDatabaseBuilder::new()
+    ///     .add_as(64497, "B")
+    ///     .add_as(64496, "A")
+    ///     .add_as(64498, "C")
+    ///     .build();
+    /// let asns: Vec<u32> = locations.autonomous_systems().map(|as_| as_.asn()).collect();
+    /// assert_eq!(asns, [64496, 64497, 64498]);
+    /// let last = locations.autonomous_systems().next_back().unwrap();
+    /// assert_eq!(last.name(), "C");
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    ///
+    /// [AS]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
+    pub fn autonomous_systems(&self) -> AutonomousSystems<'_> {
+        let inner = self.inner.get();
+        AutonomousSystems {
+            inner,
+            iter: inner.as_.iter(),
+        }
+    }
+    /// Iterate over all [AS]es (autonomous systems) in the database, ordered
+    /// by their names.
+    ///
+    /// The names are compared ignoring ASCII case. ASes with equal names are
+    /// ordered by their ASN. This sorts all ASes of the database up front.
+    ///
+    /// ```
+    /// use libloc::TestDatabaseBuilder;
+    ///
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_as(64496, "example")
+    ///     .add_as(64497, "Acme")
+    ///     .add_as(64498, "Example")
+    ///     .build();
+    ///
+    /// let asns: Vec<u32> = locations.ases_by_name().map(|as_| as_.asn()).collect();
+    /// assert_eq!(asns, [64497, 64496, 64498]);
+    /// ```
+    ///
+    /// [AS]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
+    pub fn ases_by_name(&self) -> impl Iterator<Item = As<'_>> {
+        let mut ases: Vec<As<'_>> = self.autonomous_systems().collect();
+        ases.sort_by(|a, b| {
+            let a = a.name.bytes().iter().map(u8::to_ascii_lowercase);
+            let b = b.name.bytes().iter().map(u8::to_ascii_lowercase);
+            a.cmp(b)
+        });
+        ases.into_iter()
+    }
+    /// Iterate over the [AS]es (autonomous systems) whose names contain a
+    /// query.
+    ///
+    /// The names are compared ignoring ASCII case. An empty query matches all
+    /// ASes. The ASes are ordered by their ASN. This scans all ASes of the
+    /// database.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let ases: Vec<_> = locations.find_as_by_name("lightning").collect();
+    /// assert_eq!(ases.len(), 1);
+    /// assert_eq!(ases[0].name(), "Lightning Wire Labs GmbH");
+    /// assert_eq!(locations.find_as_by_name("WIRE LABS").count(), 1);
+    /// assert_eq!(locations.find_as_by_name("example").count(), 0);
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    ///
+    /// [AS]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
+    pub fn find_as_by_name<'a>(&'a self, query: &'a str) -> impl Iterator<Item = As<'a>> + 'a {
+        let query = query.as_bytes();
+        self.autonomous_systems().filter(move |as_| {
+            query.is_empty()
+                || as_
+                    .name
+                    .bytes()
+                    .windows(query.len())
+                    .any(|window| window.eq_ignore_ascii_case(query))
+        })
+    }
+    /// Look up the [AS] of the network containing an IP address.
+    ///
+    /// Returns `None` if the address is not in any network, or if the
+    /// network's AS is unknown (AS0) or not in the database.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let as_ = locations.as_of("2a07:1c44:5800::1".parse().unwrap()).unwrap();
+    /// assert_eq!(as_.name(), "Lightning Wire Labs GmbH");
+    /// assert!(matches!(locations.as_of("127.0.0.1".parse().unwrap()), None));
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    ///
+    /// [AS]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
+    pub fn as_of(&self, addr: IpAddr) -> Option<As<'_>> {
+        let inner = self.inner.get();
+        inner.as_of(&inner.lookup(addr)?.inner)
+    }
+    /// Look up the [AS] of the network containing an IP address, together
+    /// with the number of networks and addresses it holds.
+    ///
+    /// Returns `None` in the same cases as [`Locations::as_of`].
+    ///
+    /// This walks the whole network tree, which takes time proportional to
+    /// the size of the database, so it is much slower than
+    /// [`Locations::lookup`]. Only the networks of the AS are resolved
+    /// during the walk.
+    ///
+    /// ```
+    /// use libloc::TestDatabaseBuilder;
+    ///
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network("192.0.2.0/24", 64496, "DE")
+    ///     .add_network("198.51.100.0/25", 64496, "DE")
+    ///     .add_network("2001:db8::/32", 64496, "DE")
+    ///     .add_network("203.0.113.0/24", 64497, "FR")
+    ///     .add_as(64496, "Example")
+    ///     .build();
+    ///
+    /// let footprint = locations.lookup_as_footprint("192.0.2.1".parse().unwrap()).unwrap();
+    /// assert_eq!(footprint.as_().name(), "Example");
+    /// assert_eq!(footprint.network_count(), 3);
+    /// assert_eq!(footprint.ipv4_address_count(), 256 + 128);
+    /// assert_eq!(footprint.ipv6_address_count(), 1 << 96);
+    ///
+    /// // AS64497 is not in the database.
+    /// assert!(locations.lookup_as_footprint("203.0.113.1".parse().unwrap()).is_none());
+    /// ```
+    ///
+    /// [AS]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
+    pub fn lookup_as_footprint(&self, addr: IpAddr) -> Option<AsFootprint<'_>> {
+        let inner = self.inner.get();
+        let as_ = inner.as_of(&inner.lookup(addr)?.inner)?;
+        let mut footprint = AsFootprint {
+            network_count: 0,
+            ipv4_address_count: 0,
+            ipv6_address_count: 0,
+            as_,
+        };
+        for (bits, len, network_idx) in TreeWalk::new(inner) {
+            if inner.network(network_idx).asn.get() != footprint.as_.asn {
+                continue;
+            }
+            footprint.network_count += 1;
+            match prefix(bits, len) {
+                IpNet::V4(net) => {
+                    footprint.ipv4_address_count += 1 << (32 - net.prefix_len());
+                }
+                IpNet::V6(net) => {
+                    let count = host_count(128 - net.prefix_len());
+                    footprint.ipv6_address_count =
+                        footprint.ipv6_address_count.saturating_add(count);
+                }
+            }
+        }
+        Some(footprint)
+    }
+    /// The number of IPv4 and IPv6 addresses in the networks of a country.
+    ///
+    /// Returns `(ipv4_count, ipv6_count)`. Addresses in nested networks are
+    /// counted once per network. The IPv6 count saturates at
+    /// [`u128::MAX`], which only very large blocks can reach. Like
+    /// [`Locations::country`], this ignores case and surrounding whitespace
+    /// of the code.
+    ///
+    /// This walks the whole network tree.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// assert_eq!(locations.address_count_for_country("DE"), (0, 1 << 88));
+    /// assert_eq!(locations.address_count_for_country("de"), (0, 1 << 88));
+    /// assert_eq!(locations.address_count_for_country(" DE "), (0, 1 << 88));
+    /// assert_eq!(locations.address_count_for_country("FR"), (0, 0));
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn address_count_for_country(&self, code: &str) -> (u128, u128) {
+        let code = match normalize_country_code(code) {
+            Some(code) => code,
+            None => return (0, 0),
+        };
+        self.address_count(|network| network.country_code == code)
+    }
+    /// The number of IPv4 and IPv6 addresses in the networks of an [AS].
+    ///
+    /// See [`Locations::address_count_for_country`] for details.
+    ///
+    /// ```
+    /// use libloc::TestDatabaseBuilder;
+    ///
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network("192.0.2.0/24", 64496, "DE")
+    ///     .add_network("198.51.100.0/25", 64496, "DE")
+    ///     .add_network("2001:db8::/32", 64496, "DE")
+    ///     .add_network("203.0.113.0/24", 64497, "FR")
+    ///     .build();
+    ///
+    /// assert_eq!(locations.address_count_for_asn(64496), (256 + 128, 1 << 96));
+    /// assert_eq!(locations.address_count_for_asn(64497), (256, 0));
+    /// ```
+    ///
+    /// [AS]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
+    pub fn address_count_for_asn(&self, asn: u32) -> (u128, u128) {
+        self.address_count(|network| network.asn.get() == asn)
+    }
+    fn address_count(&self, mut f: impl FnMut(&format::Network) -> bool) -> (u128, u128) {
+        let inner = self.inner.get();
+        let (mut ipv4_count, mut ipv6_count) = (0u128, 0u128);
+        for (bits, len, network_idx) in TreeWalk::new(inner) {
+            if !f(inner.network(network_idx)) {
+                continue;
+            }
+            match prefix(bits, len) {
+                IpNet::V4(net) => ipv4_count += host_count(32 - net.prefix_len()),
+                IpNet::V6(net) => {
+                    ipv6_count = ipv6_count.saturating_add(host_count(128 - net.prefix_len()));
+                }
+            }
+        }
+        (ipv4_count, ipv6_count)
+    }
+    /// Append AS and country information to a log line.
+    ///
+    /// The IP address is taken from the whitespace-separated field with
+    /// index `ip_field`, starting at 0. If it is in a network, `AS<asn>
+    /// <AS name> <country code>` is appended to the line. The AS is omitted
+    /// for AS0, the AS name if it is empty or not in the database and the
+    /// country code if it is the unknown country `XX`. Otherwise, e.g.
+    /// because the field doesn't contain an IP address, the line is returned
+    /// unchanged.
+    ///
+    /// ```
+    /// use libloc::{Locations, TestDatabaseBuilder};
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// assert_eq!(
+    ///     locations.enrich_line("GET 2a07:1c44:5800::1 /index.html", 1),
+    ///     "GET 2a07:1c44:5800::1 /index.html AS204867 Lightning Wire Labs GmbH DE",
+    /// );
+    /// assert_eq!(locations.enrich_line("GET 127.0.0.1 /", 1), "GET 127.0.0.1 /");
+    /// assert_eq!(locations.enrich_line("GET", 1), "GET");
+    ///
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network("192.0.2.0/24", 0, "DE")
+    ///     .add_network("198.51.100.0/24", 64496, "XX")
+    ///     .add_as(64496, "")
+    ///     .build();
+    /// assert_eq!(locations.enrich_line("GET 192.0.2.1 /", 1), "GET 192.0.2.1 / DE");
+    /// assert_eq!(locations.enrich_line("GET 198.51.100.1 /", 1), "GET 198.51.100.1 / AS64496");
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn enrich_line(&self, line: &str, ip_field: usize) -> String {
+        let inner = self.inner.get();
+        let network = line
+            .split_whitespace()
+            .nth(ip_field)
+            .and_then(|field| field.parse().ok())
+            .and_then(|addr| inner.lookup(addr));
+        let network = match network {
+            Some(network) => network,
+            None => return line.to_owned(),
+        };
+        let mut result = line.to_owned();
+        if network.asn() != 0 {
+            result.push_str(&format!(" AS{}", network.asn()));
+            let name = inner.as_of(&network.inner).map(|as_| as_.name_lossy());
+            if let Some(name) = name.filter(|name| !name.is_empty()) {
+                result.push(' ');
+                result.push_str(&name);
+            }
+        }
+        if network.inner.country_code != b"XX" {
+            result.push(' ');
+            result.push_str(&network.country_code_lossy());
+        }
+        result
+    }
+    /// Look up network information for an IP address.
+    ///
+    /// To look up an [`Ipv4Addr`] or [`Ipv6Addr`], use
+    /// [`Locations::lookup_v4`] or [`Locations::lookup_v6`], which also
+    /// return the more specific network types, or convert it with `.into()`.
+    ///
+    /// With the `tracing` feature, every call emits a trace-level event with
+    /// the address, the matched network, the number of bits consumed while
+    /// walking the network tree and whether a network was found.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    /// use std::net::Ipv6Addr;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// assert_eq!(locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap().asn(), 204867);
+    /// assert!(matches!(locations.lookup("127.0.0.1".parse().unwrap()), None));
+    ///
+    /// let addr: Ipv6Addr = "2a07:1c44:5800::1".parse().unwrap();
+    /// assert_eq!(locations.lookup(addr.into()).unwrap().asn(), 204867);
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn lookup(&self, addr: IpAddr) -> Option<Network<'_>> {
+        #[cfg(feature = "tracing")]
+        {
+            match self.walk_addr(addr) {
+                Ok(details) => {
+                    tracing::trace!(
+                        %addr,
+                        network = %details.network().addrs(),
+                        walked_bits = details.walked_bits(),
+                        hit = true,
+                        "lookup",
+                    );
+                    Some(details.into_network())
+                }
+                Err(walked_bits) => {
+                    tracing::trace!(%addr, walked_bits, hit = false, "lookup");
+                    None
+                }
+            }
+        }
+        #[cfg(not(feature = "tracing"))]
+        self.inner.get().lookup(addr)
+    }
+    /// Look up network information for an IP address, keeping the address.
+    ///
+    /// Like [`Locations::lookup`], but the result also holds the queried
+    /// address, which is otherwise lost in the network prefix.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let addr = "2a07:1c44:5800::1".parse().unwrap();
+    /// let result = locations.lookup_with_query(addr).unwrap();
+    /// assert_eq!(result.query(), addr);
+    /// assert_eq!(result.network().addrs().to_string(), "2a07:1c44:5800::/40");
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn lookup_with_query(&self, addr: IpAddr) -> Option<QueryMatch<'_>> {
+        self.lookup(addr).map(|network| QueryMatch {
+            query: addr,
+            network,
+        })
+    }
+    /// Look up network information for an IP address, together with details
+    /// on the tree walk that found it.
+    ///
+    /// Returns the same network as [`Locations::lookup`].
+    ///
+    /// ```
+    /// use libloc::TestDatabaseBuilder;
+    ///
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network("192.0.2.0/24", 64496, "DE")
+    ///     .add_network("192.0.2.0/26", 64497, "DE")
+    ///     .build();
+    ///
+    /// let details = locations.lookup_detailed("192.0.2.1".parse().unwrap()).unwrap();
+    /// assert_eq!(details.network().asn(), 64497);
+    /// assert_eq!((details.prefix_len(), details.walked_bits()), (26, 26));
+    /// assert!(details.is_exact());
+    /// assert!(!details.is_full_walk());
+    ///
+    /// // The tree branches towards 192.0.2.0/26 for one more bit.
+    /// let details = locations.lookup_detailed("192.0.2.65".parse().unwrap()).unwrap();
+    /// assert_eq!(details.network().asn(), 64496);
+    /// assert_eq!((details.prefix_len(), details.walked_bits()), (24, 25));
+    /// assert!(!details.is_exact());
+    /// ```
+    pub fn lookup_detailed(&self, addr: IpAddr) -> Option<LookupDetails<'_>> {
+        self.walk_addr(addr).ok()
+    }
+    /// Like [`Locations::lookup_detailed`], but returns the number of walked
+    /// bits if no network contains the address.
+    fn walk_addr(&self, addr: IpAddr) -> Result<LookupDetails<'_>, u8> {
+        let inner = self.inner.get();
+        let (root, bits_reverse, address_bits) = match addr {
+            IpAddr::V4(addr) => (
+                inner.ipv4_network_node.ok_or(0)?,
+                u32::from(addr).reverse_bits().into(),
+                32,
+            ),
+            IpAddr::V6(addr) => (0, u128::from(addr).reverse_bits(), 128),
+        };
+        let cursor = inner
+            .walk(Cursor::root(root), bits_reverse, address_bits.into())
+            .unwrap_or_else(|c| c);
+        let n = cursor.network(inner).ok_or(cursor.used_bits)?;
+        let network = match addr {
+            IpAddr::V4(addr) => inner.network_v4(addr, n).into(),
+            IpAddr::V6(addr) => inner.network_v6(addr, n).into(),
+        };
+        Ok(LookupDetails {
+            network,
+            walked_bits: cursor.used_bits,
+            address_bits,
+        })
+    }
+    /// Look up network information for an IP address, choosing how to treat
+    /// IPv4-mapped IPv6 addresses.
+    ///
+    /// With [`PreferFamily::V4`], IPv4-mapped IPv6 addresses are looked up as
+    /// the IPv4 addresses they map, returning IPv4 networks. With
+    /// [`PreferFamily::Native`], this is the same as [`Locations::lookup`].
+    /// See [`Locations::lookup_v6`] on how the two differ.
+    ///
+    /// ```
+    /// use libloc::{PreferFamily, TestDatabaseBuilder};
+    ///
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network("192.0.2.0/24", 64496, "DE")
+    ///     .build();
+    ///
+    /// let addr = "::ffff:192.0.2.1".parse().unwrap();
+    /// let network = locations.lookup_with_family(addr, PreferFamily::V4).unwrap();
+    /// assert_eq!(network.addrs().to_string(), "192.0.2.0/24");
+    /// let network = locations.lookup_with_family(addr, PreferFamily::Native).unwrap();
+    /// assert_eq!(network.addrs().to_string(), "::ffff:192.0.2.0/120");
+    /// ```
+    pub fn lookup_with_family(&self, addr: IpAddr, family: PreferFamily) -> Option<Network<'_>> {
+        let addr = match (addr, family) {
+            (IpAddr::V6(v6), PreferFamily::V4) => v6.to_ipv4_mapped().map_or(addr, IpAddr::V4),
+            _ => addr,
+        };
+        self.lookup(addr)
+    }
+    /// Whether an IPv6 address is an IPv4-mapped address, i.e. in
+    /// `::ffff:0:0/96`.
+    ///
+    /// IPv4 networks are stored in this region of the network tree. See
+    /// [`Locations::lookup_v6`] on how looking up such addresses relates to
+    /// looking up IPv4 addresses.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// assert!(Locations::is_v4_mapped_region("::ffff:192.0.2.1".parse().unwrap()));
+    /// assert!(!Locations::is_v4_mapped_region("::192.0.2.1".parse().unwrap()));
+    /// assert!(!Locations::is_v4_mapped_region("2001:db8::1".parse().unwrap()));
+    /// ```
+    pub fn is_v4_mapped_region(addr: Ipv6Addr) -> bool {
+        addr.to_ipv4_mapped().is_some()
+    }
+    /// Look up network information for an IPv4 address.
+    ///
+    /// See [`Locations::lookup`].
+    pub fn lookup_v4(&self, addr: Ipv4Addr) -> Option<NetworkV4<'_>> {
+        self.inner.get().lookup_v4(addr)
+    }
+    /// Look up network information for an IPv6 address.
+    ///
+    /// See [`Locations::lookup`].
+    ///
+    /// IPv4 networks are stored below the IPv4-mapped IPv6 addresses
+    /// `::ffff:0:0/96`, see [`Locations::is_v4_mapped_region`]. Looking up
+    /// such an address with this function finds the same networks as looking
+    /// up the corresponding IPv4 address with [`Locations::lookup_v4`], but
+    /// returns them as IPv6 networks. Additionally, it can find less specific
+    /// IPv6 networks containing all of `::ffff:0:0/96`, which
+    /// [`Locations::lookup_v4`] ignores.
+    pub fn lookup_v6(&self, addr: Ipv6Addr) -> Option<NetworkV6<'_>> {
+        self.inner.get().lookup_v6(addr)
+    }
+    /// Look up network information for an IP address, returning an error
+    /// instead of panicking if the database is corrupt.
+    ///
+    /// Like [`Locations::lookup`], this returns `Ok(None)` if the address is
+    /// not in any network. The country code of the returned network is
+    /// checked to be valid UTF-8.
+    ///
+    /// ```
+    /// use libloc::{Locations, LookupError};
+    ///
+    /// let mut data = std::fs::read("example-location.db").unwrap();
+    /// // Point all network nodes with a network at a nonexistent network.
+    /// let nodes = u32::from_be_bytes(data[44..48].try_into().unwrap()) as usize;
+    /// let nodes_len = u32::from_be_bytes(data[48..52].try_into().unwrap()) as usize;
+    /// for node in data[nodes..nodes + nodes_len].chunks_exact_mut(12) {
+    ///     if node[8..12] != [0xff; 4] {
+    ///         node[8..12].copy_from_slice(&1000u32.to_be_bytes());
+    ///     }
+    /// }
+    /// let locations = Locations::from_bytes(data)?;
+    ///
+    /// assert!(matches!(
+    ///     locations.try_lookup("2a07:1c44:5800::1".parse().unwrap()),
+    ///     Err(LookupError::InvalidIndex { what: "network", index: 1000, len: 1 }),
+    /// ));
+    /// assert!(matches!(locations.try_lookup("127.0.0.1".parse().unwrap()), Ok(None)));
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn try_lookup(&self, addr: IpAddr) -> Result<Option<Network<'_>>, LookupError> {
+        self.inner.get().try_lookup(addr)
+    }
+    /// Look up network information for an IPv4 address, returning an error
+    /// instead of panicking if the database is corrupt.
+    ///
+    /// See [`Locations::try_lookup`].
+    pub fn try_lookup_v4(&self, addr: Ipv4Addr) -> Result<Option<NetworkV4<'_>>, LookupError> {
+        self.inner.get().try_lookup_v4(addr)
+    }
+    /// Look up network information for an IPv6 address, returning an error
+    /// instead of panicking if the database is corrupt.
+    ///
+    /// See [`Locations::try_lookup`] and [`Locations::lookup_v6`].
+    pub fn try_lookup_v6(&self, addr: Ipv6Addr) -> Result<Option<NetworkV6<'_>>, LookupError> {
+        self.inner.get().try_lookup_v6(addr)
+    }
+    /// Look up network information for an IP address, together with the
+    /// first and last address of the network as integers.
+    ///
+    /// IPv4 addresses are converted to integers by [`u32::from`], i.e. they
+    /// occupy the lowest 32 bits and are *not* IPv4-mapped. Thus IPv4 and
+    /// IPv6 bounds overlap, store the address family alongside if you mix
+    /// both, see [`Network::addrs`]. Both bounds are inclusive.
+    ///
+    /// ```
+    /// use libloc::TestDatabaseBuilder;
+    ///
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network("192.0.2.0/24", 64496, "DE")
+    ///     .add_network("2001:db8::/32", 64496, "DE")
+    ///     .build();
+    ///
+    /// let (first, last, network) = locations.lookup_bounds("192.0.2.1".parse().unwrap()).unwrap();
+    /// assert_eq!((first, last), (0xc000_0200, 0xc000_02ff));
+    /// assert_eq!(network.asn(), 64496);
+    ///
+    /// let (first, last, _) = locations.lookup_bounds("2001:db8::1".parse().unwrap()).unwrap();
+    /// assert_eq!(first, 0x2001_0db8 << 96);
+    /// assert_eq!(last, (0x2001_0db8 << 96) | (1 << 96) - 1);
+    /// ```
+    pub fn lookup_bounds(&self, addr: IpAddr) -> Option<(u128, u128, Network<'_>)> {
+        let network = self.lookup(addr)?;
+        let (first, last) = match network.addrs {
+            IpNet::V4(addrs) => (
+                u32::from(addrs.network()).into(),
+                u32::from(addrs.broadcast()).into(),
+            ),
+            IpNet::V6(addrs) => (addrs.network().into(), addrs.broadcast().into()),
+        };
+        Some((first, last, network))
+    }
+    /// Write all networks of the database as [JSON Lines].
+    ///
+    /// Each line is an object like the following, with `as_name` being
+    /// `null` if the AS is not in the database:
+    ///
+    /// ```json
+    /// {"network":"2a07:1c44:5800::/40","asn":204867,"as_name":"Lightning Wire Labs GmbH","country_code":"DE","flags":["anycast"]}
+    /// ```
+    ///
+    /// The networks are written in the order of [`Locations::networks`].
+    /// Output is buffered and written as it is produced, so this works for
+    /// databases of any size. Every AS name is only resolved once. Invalid
+    /// UTF-8 is replaced, see [`String::from_utf8_lossy`].
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let mut jsonl = Vec::new();
+    /// locations.to_jsonl(&mut jsonl).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(jsonl).unwrap(),
+    ///     "{\"network\":\"2a07:1c44:5800::/40\",\"asn\":204867,\
+    ///      \"as_name\":\"Lightning Wire Labs GmbH\",\"country_code\":\"DE\",\
+    ///      \"flags\":[\"anycast\"]}\n",
+    /// );
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    ///
+    /// [JSON Lines]: https://jsonlines.org/
+    pub fn to_jsonl<W: Write>(&self, w: W) -> io::Result<()> {
+        let inner = self.inner.get();
+        let mut w = io::BufWriter::new(w);
+        // The AS names as JSON values, by ASN.
+        let mut as_names: HashMap<u32, Vec<u8>> = HashMap::new();
+        for network in self.networks() {
+            write!(
+                w,
+                "{{\"network\":\"{}\",\"asn\":{},\"as_name\":",
+                network.addrs,
+                network.asn(),
+            )?;
+            let as_name = as_names.entry(network.asn()).or_insert_with(|| {
+                let mut as_name = Vec::new();
+                match inner.as_of(&network.inner) {
+                    Some(as_) => write_json_string(&mut as_name, &as_.name_lossy()).unwrap(),
+                    None => as_name.extend_from_slice(b"null"),
+                }
+                as_name
+            });
+            w.write_all(as_name)?;
+            w.write_all(b",\"country_code\":")?;
+            write_json_string(&mut w, &network.country_code_lossy())?;
+            w.write_all(b",\"flags\":[")?;
+            for (i, name) in network.flag_names().into_iter().enumerate() {
+                if i != 0 {
+                    w.write_all(b",")?;
+                }
+                write_json_string(&mut w, name)?;
+            }
+            w.write_all(b"]}\n")?;
+        }
+        w.flush()
+    }
+    /// Write all networks as [JSON Lines], using their `Serialize`
+    /// implementation.
+    ///
+    /// Each line is an object like the following, see [`Network`]'s
+    /// `Serialize` implementation:
+    ///
+    /// ```json
+    /// {"addrs":"2a07:1c44:5800::/40","country_code":"DE","asn":204867,"is_anonymous_proxy":false,"is_satellite_provider":false,"is_anycast":true,"is_drop":false}
+    /// ```
+    ///
+    /// Unlike [`Locations::to_jsonl`], this doesn't include AS names. It is
+    /// only available with the `json` feature.
+    ///
+    /// The networks are written in the order of [`Locations::networks`].
+    /// Output is buffered and written as it is produced, so this works for
+    /// databases of any size.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let mut jsonl = Vec::new();
+    /// locations.write_networks_jsonl(&mut jsonl).unwrap();
+    /// let jsonl = String::from_utf8(jsonl).unwrap();
+    /// assert_eq!(jsonl.lines().count(), 1);
+    /// let network: serde_json::Value = serde_json::from_str(jsonl.lines().next().unwrap()).unwrap();
+    /// assert_eq!(network["addrs"], "2a07:1c44:5800::/40");
+    /// assert_eq!(network["is_anycast"], true);
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    ///
+    /// [JSON Lines]: https://jsonlines.org/
+    #[cfg(feature = "json")]
+    pub fn write_networks_jsonl<W: Write>(&self, w: W) -> io::Result<()> {
+        let mut w = io::BufWriter::new(w);
+        for network in self.networks() {
+            serde_json::to_writer(&mut w, &network)?;
+            w.write_all(b"\n")?;
+        }
+        w.flush()
+    }
+    /// Write the database as a [MaxMind DB] (MMDB) file.
+    ///
+    /// The data of each network is a map with the `country` (a map with the
+    /// `iso_code`), `autonomous_system_number` and
+    /// `autonomous_system_organization` keys, the same as in MaxMind's
+    /// country and ASN databases. Keys are omitted for the unknown country
+    /// `XX`, for AS0 and for ASes without a name in the database. Flags are
+    /// not exported.
+    ///
+    /// The file is an IPv6 database. IPv4 networks are stored at
+    /// `::ffff:0:0/96` like in libloc, and also aliased at `::/96`, where
+    /// MMDB readers look up IPv4 addresses. The database is built in memory
+    /// before it is written. It is only available with the `mmdb` feature.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    /// use std::collections::BTreeMap;
+    /// use std::net::IpAddr;
+    ///
+    /// let locations = libloc::TestDatabaseBuilder::new()
+    ///     .add_network("192.0.2.0/24", 64496, "DE")
+    ///     .add_network("2001:db8::/32", 64497, "XX")
+    ///     .add_as(64496, "Example")
+    ///     .add_as(64497, &"Long name ".repeat(30))
+    ///     .build();
+    /// let mut mmdb = Vec::new();
+    /// locations.to_mmdb(&mut mmdb).unwrap();
+    ///
+    /// let reader = maxminddb::Reader::from_source(mmdb).unwrap();
+    /// let lookup = |addr: &str| {
+    ///     let addr: IpAddr = addr.parse().unwrap();
+    ///     reader.lookup::<serde_json::Value>(addr).ok()
+    /// };
+    /// let data = lookup("192.0.2.1").unwrap();
+    /// assert_eq!(data["country"]["iso_code"], "DE");
+    /// assert_eq!(data["autonomous_system_number"], 64496);
+    /// assert_eq!(data["autonomous_system_organization"], "Example");
+    /// assert_eq!(lookup("::ffff:192.0.2.1"), Some(data));
+    ///
+    /// let data = lookup("2001:db8::1").unwrap();
+    /// assert_eq!(data["autonomous_system_number"], 64497);
+    /// assert_eq!(data["autonomous_system_organization"], "Long name ".repeat(30));
+    /// assert!(data.get("country").is_none());
+    /// assert!(lookup("198.51.100.1").is_none());
+    /// ```
+    ///
+    /// [MaxMind DB]: https://maxmind.github.io/MaxMind-DB/
+    #[cfg(feature = "mmdb")]
+    pub fn to_mmdb<W: Write>(&self, w: W) -> io::Result<()> {
+        mmdb::write(self.inner.get(), w)
+    }
+    /// Write all networks as [CSV].
+    ///
+    /// The first line is a header with the columns `network`, `asn`,
+    /// `country`, `anonymous_proxy`, `satellite`, `anycast` and `drop`. Each
+    /// following line describes one network, the flags being `1` if set and
+    /// `0` otherwise.
+    ///
+    /// The networks are written in the order of [`Locations::networks`].
+    /// Output is buffered and written as it is produced, so this works for
+    /// databases of any size. Invalid UTF-8 is replaced, see
+    /// [`String::from_utf8_lossy`].
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let mut csv = Vec::new();
+    /// locations.write_networks_csv(&mut csv).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(csv).unwrap(),
+    ///     "network,asn,country,anonymous_proxy,satellite,anycast,drop\n\
+    ///      2a07:1c44:5800::/40,204867,DE,0,0,1,0\n",
+    /// );
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    ///
+    /// [CSV]: https://www.rfc-editor.org/rfc/rfc4180
+    pub fn write_networks_csv<W: Write>(&self, w: W) -> io::Result<()> {
+        let mut w = io::BufWriter::new(w);
+        w.write_all(b"network,asn,country,anonymous_proxy,satellite,anycast,drop\n")?;
+        for network in self.networks() {
+            write!(w, "{},{},", network.addrs, network.asn())?;
+            write_csv_field(&mut w, &network.country_code_lossy())?;
+            let flags = network.flags();
+            for flag in [
+                NetworkFlags::ANONYMOUS_PROXY,
+                NetworkFlags::SATELLITE_PROVIDER,
+                NetworkFlags::ANYCAST,
+                NetworkFlags::DROP,
+            ] {
+                write!(w, ",{}", flags.contains(flag) as u8)?;
+            }
+            w.write_all(b"\n")?;
+        }
+        w.flush()
+    }
+    /// Write the network tree as a [Graphviz] DOT graph.
+    ///
+    /// Nodes carrying a network are labeled with its prefix, ASN and country
+    /// code. At most `max_nodes` nodes are written, in depth-first order, as
+    /// real databases have millions of nodes.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let mut dot = Vec::new();
+    /// locations.to_dot(&mut dot, 100).unwrap();
+    /// let dot = String::from_utf8(dot).unwrap();
+    /// assert!(dot.starts_with("digraph libloc {"));
+    /// assert!(dot.contains("2a07:1c44:5800::/40\\nAS204867 DE"));
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    ///
+    /// [Graphviz]: https://graphviz.org/
+    pub fn to_dot<W: Write>(&self, w: W, max_nodes: usize) -> io::Result<()> {
+        let inner = self.inner.get();
+        let mut w = w;
+        writeln!(w, "digraph libloc {{")?;
+        // (node, parent, bits, depth), with the bits in network order.
+        let mut stack = vec![(0, None, 0u128, 0u8)];
+        let mut written = 0;
+        while let Some((index, parent, bits, depth)) = stack.pop() {
+            if written >= max_nodes {
+                break;
+            }
+            written += 1;
+            let node = inner.network_node(index);
+            match node.network() {
+                Some(network_idx) => {
+                    let network = inner.network(network_idx);
+                    let prefix = prefix_v6(bits, depth);
+                    let label = format!(
+                        "{}\nAS{} {}",
+                        prefix,
+                        network.asn.get(),
+                        String::from_utf8_lossy(&network.country_code),
+                    );
+                    writeln!(w, "    n{} [label={:?}, shape=box];", index, label)?;
+                }
+                None => writeln!(w, "    n{} [label=\"\", shape=point];", index)?,
+            }
+            if let Some((parent, bit)) = parent {
+                writeln!(w, "    n{} -> n{} [label=\"{}\"];", parent, index, bit)?;
+            }
+            for bit in [1, 0] {
+                let child = node.children[bit].get();
+                if child != 0 {
+                    stack.push((
+                        child,
+                        Some((index, bit)),
+                        bits << 1 | bit as u128,
+                        depth + 1,
+                    ));
+                }
+            }
+        }
+        writeln!(w, "}}")?;
+        Ok(())
+    }
+    /// Lock the AS, country and string tables of the database into memory.
+    ///
+    /// After this, resolving the AS or country of a network never waits for
+    /// these tables to be read from disk. Unlike locking the whole database,
+    /// this leaves out the large network tree.
+    ///
+    /// Only memory-mapped databases can be pinned, see
+    /// [`Locations::backing`]. The tables stay locked until the memory
+    /// mapping is unmapped, which happens when the last clone of this
+    /// `Locations` sharing it is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`io::ErrorKind::Unsupported`] for databases
+    /// not backed by a memory mapping, e.g. those created by
+    /// [`Locations::from_bytes`]. Errors from `mlock(2)` are returned, e.g.
+    /// when exceeding the limit of locked memory (`RLIMIT_MEMLOCK`).
+    ///
+    /// ```
+    /// use libloc::{Locations, TestDatabaseBuilder};
+    /// use std::io;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// locations.pin_tables().unwrap();
+    ///
+    /// let owned = TestDatabaseBuilder::new().build();
+    /// assert_eq!(owned.pin_tables().unwrap_err().kind(), io::ErrorKind::Unsupported);
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    #[cfg(unix)]
+    pub fn pin_tables(&self) -> io::Result<()> {
+        // Locks on heap memory would outlive the buffer, as nothing unlocks
+        // them when it's freed. Unmapping releases the locks of a mapping.
+        if self.backing() != Backing::MmapFile {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "only memory-mapped databases can be pinned",
+            ));
+        }
+        let inner = self.inner.get();
+        mlock(inner.as_.as_bytes())?;
+        mlock(inner.countries.as_bytes())?;
+        mlock(inner.string_pool)?;
+        Ok(())
+    }
+    /// The number of nodes in the network tree.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// assert_eq!(locations.network_node_count(), 41);
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn network_node_count(&self) -> usize {
+        self.inner.get().network_nodes.len()
+    }
+    /// The number of entries in the database and its size.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let stats = locations.stats();
+    /// assert_eq!(stats.as_count, 1);
+    /// assert_eq!(stats.network_count, 1);
+    /// assert_eq!(stats.network_node_count, 41);
+    /// assert_eq!(stats.country_count, 1);
+    /// assert_eq!(stats.data_len, 24660);
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn stats(&self) -> Stats {
+        let inner = self.inner.get();
+        Stats {
+            as_count: inner.as_.len(),
+            network_count: inner.networks.len(),
+            network_node_count: inner.network_nodes.len(),
+            country_count: inner.countries.len(),
+            string_pool_len: inner.string_pool.len(),
+            data_len: self.inner.backing_cart().len(),
+        }
+    }
+    /// The length of the longest path from the root of the network tree to
+    /// one of its nodes.
+    ///
+    /// This is the maximum number of steps of a tree walk in
+    /// [`Locations::lookup`]. This visits all nodes of the network tree.
+    /// Children not following their parent in the network node table are
+    /// ignored, see [`Locations::verify_trie_acyclic`].
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// // The only network is a /40.
+    /// assert_eq!(locations.tree_depth(), 40);
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn tree_depth(&self) -> u32 {
+        let inner = self.inner.get();
+        // The network nodes are stored in topological order, so the depth of
+        // each node is known before its children are visited.
+        let mut depths: Vec<Option<u32>> = vec![None; inner.network_nodes.len()];
+        if let Some(root) = depths.first_mut() {
+            *root = Some(0);
+        }
+        let mut max_depth = 0;
+        for (node, network_node) in inner.network_nodes.iter().enumerate() {
+            let depth = match depths[node] {
+                Some(depth) => depth,
+                None => continue,
+            };
+            max_depth = max_depth.max(depth);
+            for child in network_node.children.iter().map(|c| c.get() as usize) {
+                if node < child && child < depths.len() {
+                    let child_depth = depths[child].get_or_insert(0);
+                    *child_depth = (*child_depth).max(depth + 1);
+                }
+            }
+        }
+        max_depth
+    }
+    /// Reconstruct the prefix of a node in the network tree from the path
+    /// leading to it.
+    ///
+    /// `path_bits` contains the children taken on the path, the first one in
+    /// the most significant and the last one in the least significant of the
+    /// `depth` lowest bits, i.e. it is built by `bits = bits << 1 | child`
+    /// for each step. Higher bits are ignored. For [`AddrFamily::V6`], the
+    /// path starts at the root of the network tree. For [`AddrFamily::V4`],
+    /// it starts at the node of `::ffff:0:0/96`, below which IPv4 networks
+    /// are stored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `depth` is larger than the address width of `family`.
+    ///
+    /// ```
+    /// use libloc::{AddrFamily, Locations};
+    ///
+    /// let prefix = Locations::node_prefix(0b1100_0000_0000_0000_0000_0010, 24, AddrFamily::V4);
+    /// assert_eq!(prefix, "192.0.2.0/24".parse().unwrap());
+    /// let prefix = Locations::node_prefix(0x2001_0db8, 32, AddrFamily::V6);
+    /// assert_eq!(prefix, "2001:db8::/32".parse().unwrap());
+    /// assert_eq!(Locations::node_prefix(0, 0, AddrFamily::V6), "::/0".parse().unwrap());
+    /// ```
+    pub fn node_prefix(path_bits: u128, depth: u32, family: AddrFamily) -> IpNet {
+        let width = match family {
+            AddrFamily::V4 => 32,
+            AddrFamily::V6 => 128,
+        };
+        assert!(
+            depth <= width,
+            "depth {} exceeds the {:?} address width",
+            depth,
+            family,
+        );
+        let depth = depth as u8;
+        match family {
+            AddrFamily::V4 => {
+                let addr = (path_bits as u32)
+                    .checked_shl(32 - u32::from(depth))
+                    .unwrap_or(0);
+                Ipv4Net::new(addr.into(), depth).unwrap().into()
+            }
+            AddrFamily::V6 => prefix_v6(path_bits, depth).into(),
+        }
+    }
+    /// Check that the network tree only points forward.
+    ///
+    /// libloc writes the network nodes in topological order, so every child
+    /// of a node must be in range and come after it in the network node
+    /// table. This guarantees that the tree has no cycles. Violations
+    /// indicate a corrupt database or a non-conforming writer.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// assert!(locations.verify_trie_acyclic().is_ok());
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn verify_trie_acyclic(&self) -> Result<(), TrieError> {
+        let inner = self.inner.get();
+        let len = inner.network_nodes.len();
+        for (node, network_node) in inner.network_nodes.iter().enumerate() {
+            for child in network_node.children.iter().map(|c| c.get()) {
+                if child == 0 {
+                    continue;
+                }
+                // The database can't have more than `u32::MAX` nodes.
+                let node = node as u32;
+                if child as usize >= len {
+                    return Err(TrieError::ChildOutOfRange { node, child });
+                }
+                if child <= node {
+                    return Err(TrieError::ChildNotForward { node, child });
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Check that all references within the database are valid.
+    ///
+    /// [`Locations::open`] only checks that the sections of the database are
+    /// in bounds. This additionally checks that every network node only
+    /// references existing network nodes and networks, and that every
+    /// string is in bounds, null-terminated and valid UTF-8. Databases
+    /// passing this check never cause panics on lookups.
+    ///
+    /// The first invalid record found is returned as an error.
+    ///
+    /// ```
+    /// use libloc::{Locations, LookupError, ValidationError};
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// assert!(locations.validate().is_ok());
+    ///
+    /// let mut data = std::fs::read("example-location.db").unwrap();
+    /// // Point the AS at a string past the end of the string pool.
+    /// let as_ = u32::from_be_bytes(data[28..32].try_into().unwrap()) as usize;
+    /// data[as_ + 4..as_ + 8].copy_from_slice(&u32::MAX.to_be_bytes());
+    /// let locations = Locations::from_bytes(data)?;
+    /// assert!(matches!(
+    ///     locations.validate(),
+    ///     Err(ValidationError::InvalidAs {
+    ///         as_: 0,
+    ///         error: LookupError::InvalidStrRef(u32::MAX),
+    ///     }),
+    /// ));
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        use self::ValidationError::*;
+
+        fn index(i: usize) -> u32 {
+            // Sections can't have more than `u32::MAX` records.
+            i as u32
         }
-    }
-}
 
-impl<'a> LocationsInner<'a> {
-    fn find_network(&self, root: u32, bits_reverse: u128, num_bits: u32) -> Option<(u8, u32)> {
-        // Walk the tree, remembering the last network we saw.
-        let mut used_bits = 0;
-        let mut bits = bits_reverse;
-        let mut cur = self.network_node(root);
-        let mut last_network = None;
-        for _ in 0..num_bits {
-            let next_index = cur.children[(bits & 1 != 0) as usize].get();
-            if next_index == 0 {
-                break;
+        let inner = self.inner.get();
+        let header = inner.header;
+        for &(field, str_ref) in &[
+            ("vendor", header.vendor),
+            ("description", header.description),
+            ("license", header.license),
+        ] {
+            inner
+                .try_string_bytes(str_ref)
+                .and_then(|bytes| try_from_utf8(bytes, field))
+                .map_err(|error| InvalidHeader { field, error })?;
+        }
+        for (node, network_node) in inner.network_nodes.iter().enumerate() {
+            let children = network_node.children.iter().map(|c| c.get());
+            for child in children.filter(|&c| c != 0) {
+                inner
+                    .try_network_node(child)
+                    .map_err(|error| InvalidNetworkNode {
+                        node: index(node),
+                        error,
+                    })?;
             }
-            last_network = cur.network().map(|n| (used_bits, n)).or(last_network);
-            bits >>= 1;
-            used_bits += 1;
-            cur = self.network_node(next_index);
+            if let Some(network) = network_node.network() {
+                inner
+                    .try_network(network)
+                    .map_err(|error| InvalidNetworkNode {
+                        node: index(node),
+                        error,
+                    })?;
+            }
+        }
+        for (network, record) in inner.networks.iter().enumerate() {
+            try_from_utf8(&record.country_code, "network country code").map_err(|error| {
+                InvalidNetwork {
+                    network: index(network),
+                    error,
+                }
+            })?;
+        }
+        for (as_, record) in inner.as_.iter().enumerate() {
+            inner
+                .try_string_bytes(record.name)
+                .and_then(|name| try_from_utf8(name, "AS name"))
+                .map_err(|error| InvalidAs {
+                    as_: index(as_),
+                    error,
+                })?;
+        }
+        for (country, record) in inner.countries.iter().enumerate() {
+            try_from_utf8(&record.code, "country code")
+                .and_then(|_| try_from_utf8(&record.continent_code, "country continent code"))
+                .and_then(|_| inner.try_string_bytes(record.name))
+                .and_then(|name| try_from_utf8(name, "country name"))
+                .map_err(|error| InvalidCountry {
+                    country: index(country),
+                    error,
+                })?;
         }
-        last_network = cur.network().map(|n| (used_bits, n)).or(last_network);
-        last_network
+        Ok(())
     }
-    fn find_network_node(&self, root: u32, bits_reverse: u128, num_bits: u32) -> Option<u32> {
-        // Walk the tree.
-        let mut bits = bits_reverse;
-        let mut cur_index = root;
-        for _ in 0..num_bits {
-            cur_index = self.network_node(cur_index).children[(bits & 1 != 0) as usize].get();
-            if cur_index == 0 {
-                return None;
+    /// Check the database for corruption, including the format of codes.
+    ///
+    /// In addition to [`Locations::validate`], this checks that the code and
+    /// continent code of every country are two ASCII uppercase letters, and
+    /// that the country code of every network is as well. These checks catch
+    /// corrupt databases whose codes are still valid UTF-8.
+    ///
+    /// ```
+    /// use libloc::{Locations, ValidationError};
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// assert!(locations.validate_strict().is_ok());
+    ///
+    /// let mut data = std::fs::read("example-location.db").unwrap();
+    /// // Change the country code of the first country from "DE" to "D3".
+    /// let countries = u32::from_be_bytes(data[52..56].try_into().unwrap()) as usize;
+    /// data[countries + 1] = b'3';
+    /// let locations = Locations::from_bytes(data)?;
+    /// assert!(locations.validate().is_ok());
+    /// assert!(matches!(
+    ///     locations.validate_strict(),
+    ///     Err(ValidationError::InvalidCountryCode {
+    ///         country: 0,
+    ///         field: "code",
+    ///         code: [b'D', b'3'],
+    ///     }),
+    /// ));
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn validate_strict(&self) -> Result<(), ValidationError> {
+        use self::ValidationError::*;
+
+        fn is_code(code: &[u8; 2]) -> bool {
+            code.iter().all(u8::is_ascii_uppercase)
+        }
+
+        self.validate()?;
+        let inner = self.inner.get();
+        for (record, network) in inner.networks.iter().zip(0..) {
+            // "XX" is two uppercase letters as well.
+            if !is_code(&record.country_code) {
+                return Err(InvalidNetworkCountryCode {
+                    network,
+                    code: record.country_code,
+                });
             }
-            bits >>= 1;
         }
-        Some(cur_index)
-    }
-    fn as_(&self, index: u32) -> &'a format::As {
-        let index = index as usize;
-        if index >= self.as_.len() {
-            panic!(
-                "corrupt libloc db: invalid as index: {} > {}",
-                index,
-                self.as_.len(),
-            );
+        for (record, country) in inner.countries.iter().zip(0..) {
+            for &(field, code) in &[
+                ("code", record.code),
+                ("continent_code", record.continent_code),
+            ] {
+                if !is_code(&code) {
+                    return Err(InvalidCountryCode {
+                        country,
+                        field,
+                        code,
+                    });
+                }
+            }
         }
-        &self.as_[index]
+        Ok(())
     }
-    fn network(&self, index: u32) -> &'a format::Network {
-        let index = index as usize;
-        if index >= self.networks.len() {
-            panic!(
-                "corrupt libloc db: invalid network index: {} > {}",
-                index,
-                self.networks.len(),
-            );
+    /// Look up all networks with the most specific prefix containing an IP
+    /// address.
+    ///
+    /// This is meant for auditing databases for ambiguous assignments. In the
+    /// libloc format, each node of the network tree carries at most one
+    /// network, so the result currently contains at most one network, the
+    /// one returned by [`Locations::lookup`]. See
+    /// [`Locations::covering_networks`] for the less specific networks.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let networks = locations.lookup_all_at("2a07:1c44:5800::1".parse().unwrap());
+    /// assert_eq!(networks.len(), 1);
+    /// assert_eq!(networks[0].asn(), 204867);
+    /// assert!(locations.lookup_all_at("127.0.0.1".parse().unwrap()).is_empty());
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn lookup_all_at(&self, addr: IpAddr) -> Vec<Network<'_>> {
+        let mut networks = self.covering_networks(addr);
+        let most_specific = networks.last().map(|network| network.addrs.prefix_len());
+        networks.retain(|network| Some(network.addrs.prefix_len()) == most_specific);
+        networks
+    }
+    /// Look up all networks containing an IP address.
+    ///
+    /// The networks are ordered from the least specific one to the most
+    /// specific one, which is the one returned by [`Locations::lookup`].
+    ///
+    /// ```
+    /// use libloc::TestDatabaseBuilder;
+    ///
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network("10.0.0.0/8", 64496, "DE")
+    ///     .add_network("10.1.0.0/16", 64497, "DE")
+    ///     .add_network("10.1.2.0/24", 64498, "FR")
+    ///     .build();
+    ///
+    /// let networks = locations.covering_networks("10.1.2.3".parse().unwrap());
+    /// let prefixes: Vec<_> = networks.iter().map(|n| n.addrs().to_string()).collect();
+    /// assert_eq!(prefixes, ["10.0.0.0/8", "10.1.0.0/16", "10.1.2.0/24"]);
+    ///
+    /// assert_eq!(locations.covering_networks("10.2.0.1".parse().unwrap()).len(), 1);
+    /// assert!(locations.covering_networks("127.0.0.1".parse().unwrap()).is_empty());
+    /// ```
+    pub fn covering_networks(&self, addr: IpAddr) -> Vec<Network<'_>> {
+        let inner = self.inner.get();
+        inner
+            .path_of(addr)
+            .map(|n| match addr {
+                IpAddr::V4(addr) => inner.network_v4(addr, n).into(),
+                IpAddr::V6(addr) => inner.network_v6(addr, n).into(),
+            })
+            .collect()
+    }
+    /// Look up all networks containing an IP address, most specific first.
+    ///
+    /// The networks are ordered by decreasing prefix length, starting with
+    /// the one returned by [`Locations::lookup`]. This is the reverse order
+    /// of [`Locations::covering_networks`].
+    ///
+    /// ```
+    /// use libloc::TestDatabaseBuilder;
+    ///
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network("2001:db8::/32", 64496, "DE")
+    ///     .add_network("2001:db8:1::/48", 64497, "FR")
+    ///     .add_network("2001:db8:1:2::1/128", 64498, "FR")
+    ///     .build();
+    ///
+    /// let networks = locations.lookup_ancestors("2001:db8:1:2::1".parse().unwrap());
+    /// let prefixes: Vec<_> = networks.iter().map(|n| n.addrs().to_string()).collect();
+    /// assert_eq!(prefixes, ["2001:db8:1:2::1/128", "2001:db8:1::/48", "2001:db8::/32"]);
+    ///
+    /// let networks = locations.lookup_ancestors("2001:db8:1:2::2".parse().unwrap());
+    /// let asns: Vec<_> = networks.iter().map(|n| n.asn()).collect();
+    /// assert_eq!(asns, [64497, 64496]);
+    ///
+    /// assert!(locations.lookup_ancestors("2001:db9::1".parse().unwrap()).is_empty());
+    /// ```
+    pub fn lookup_ancestors(&self, addr: IpAddr) -> Vec<Network<'_>> {
+        let mut networks = self.covering_networks(addr);
+        networks.reverse();
+        networks
+    }
+    /// Look up network information for an [`ipnetwork::IpNetwork`].
+    ///
+    /// Returns the most specific network containing all addresses of `net`.
+    /// Host bits of `net` are ignored. The prefix of the result can be
+    /// converted back into the `ipnetwork` types via [`From`].
+    ///
+    /// ```
+    /// use ipnetwork::IpNetwork;
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let net: IpNetwork = "2a07:1c44:5800::/48".parse().unwrap();
+    /// let network = locations.lookup_ipnetwork(net).unwrap();
+    /// assert_eq!(network.asn(), 204867);
+    /// assert_eq!(IpNetwork::from(&network), "2a07:1c44:5800::/40".parse().unwrap());
+    ///
+    /// // Not completely contained in `2a07:1c44:5800::/40`.
+    /// assert!(locations.lookup_ipnetwork("2a07:1c44::/32".parse().unwrap()).is_none());
+    ///
+    /// let locations = libloc::TestDatabaseBuilder::new()
+    ///     .add_network("192.0.2.0/24", 64496, "DE")
+    ///     .build();
+    /// let network = locations.lookup_ipnetwork("192.0.2.128/25".parse().unwrap()).unwrap();
+    /// assert_eq!(IpNetwork::from(&network), "192.0.2.0/24".parse().unwrap());
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    #[cfg(feature = "ipnetwork")]
+    pub fn lookup_ipnetwork(&self, net: ipnetwork::IpNetwork) -> Option<Network<'_>> {
+        self.lookup_net(from_ipnetwork(net))
+    }
+    /// Look up the network containing all addresses of a prefix.
+    ///
+    /// Returns the most specific network that is equal to `net` or a
+    /// supernet of it. Returns `None` if there is no such network, in
+    /// particular if the most specific network containing the first address
+    /// of `net` is more specific than `net`. Host bits of `net` are ignored.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let network = locations.lookup_net("2a07:1c44:5800::/48".parse().unwrap()).unwrap();
+    /// assert_eq!(network.addrs().to_string(), "2a07:1c44:5800::/40");
+    /// let network = locations.lookup_net("2a07:1c44:5800::/40".parse().unwrap()).unwrap();
+    /// assert_eq!(network.addrs().to_string(), "2a07:1c44:5800::/40");
+    ///
+    /// // Broader than `2a07:1c44:5800::/40`.
+    /// assert!(locations.lookup_net("2a07:1c44::/32".parse().unwrap()).is_none());
+    ///
+    /// let locations = libloc::TestDatabaseBuilder::new()
+    ///     .add_network("192.0.0.0/8", 64496, "DE")
+    ///     .add_network("192.0.0.0/24", 64497, "FR")
+    ///     .build();
+    /// // The first address is in `192.0.0.0/24`, more specific than the query.
+    /// assert!(locations.lookup_net("192.0.0.0/16".parse().unwrap()).is_none());
+    /// let network = locations.lookup_net("192.1.0.0/16".parse().unwrap()).unwrap();
+    /// assert_eq!(network.addrs().to_string(), "192.0.0.0/8");
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn lookup_net(&self, net: IpNet) -> Option<Network<'_>> {
+        let network = self.lookup(net.network())?;
+        if network.addrs().prefix_len() > net.prefix_len() {
+            return None;
         }
-        &self.networks[index]
+        Some(network)
     }
-    fn network_node(&self, index: u32) -> &'a format::NetworkNode {
-        let index = index as usize;
-        if index >= self.network_nodes.len() {
-            panic!(
-                "corrupt libloc db: invalid network node index: {} > {}",
-                index,
-                self.network_nodes.len(),
-            );
+    /// Look up an IP address or prefix.
+    ///
+    /// Dispatches to [`Locations::lookup`] for addresses and to
+    /// [`Locations::lookup_net`] for prefixes.
+    ///
+    /// ```
+    /// use libloc::{Locations, Query};
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// for query in ["2a07:1c44:5800::1", "2a07:1c44:5800::/48"] {
+    ///     let query: Query = query.parse().unwrap();
+    ///     let network = locations.lookup_query(&query).unwrap();
+    ///     assert_eq!(network.addrs().to_string(), "2a07:1c44:5800::/40");
+    /// }
+    /// assert!(locations.lookup_query(&"2a07:1c44::/32".parse().unwrap()).is_none());
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn lookup_query(&self, query: &Query) -> Option<Network<'_>> {
+        match *query {
+            Query::Addr(addr) => self.lookup(addr),
+            Query::Net(net) => self.lookup_net(net),
         }
-        &self.network_nodes[index]
     }
-    fn country(&self, index: u32) -> &'a format::Country {
-        let index = index as usize;
-        if index >= self.countries.len() {
-            panic!(
-                "corrupt libloc db: invalid country index: {} > {}",
-                index,
-                self.countries.len(),
-            );
+    /// Whether an IP address is in any [anycast] network.
+    ///
+    /// Unlike [`Network::is_anycast`] on the result of [`Locations::lookup`],
+    /// this also considers the less specific networks containing the address,
+    /// see [`Locations::covering_networks`]. Anycast is often only marked on
+    /// a broad aggregate.
+    ///
+    /// ```
+    /// use libloc::{NetworkFlags, TestDatabaseBuilder};
+    ///
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network_with_flags("192.0.2.0/24", 64496, "DE", NetworkFlags::ANYCAST)
+    ///     .add_network("192.0.2.0/25", 64496, "DE")
+    ///     .build();
+    ///
+    /// let addr = "192.0.2.1".parse().unwrap();
+    /// assert!(!locations.lookup(addr).unwrap().is_anycast());
+    /// assert!(locations.is_anycast_addr(addr));
+    /// assert!(!locations.is_anycast_addr("198.51.100.1".parse().unwrap()));
+    /// ```
+    ///
+    /// [anycast]: https://en.wikipedia.org/wiki/Anycast
+    pub fn is_anycast_addr(&self, addr: IpAddr) -> bool {
+        let inner = self.inner.get();
+        inner.path_of(addr).any(|(_, network_idx)| {
+            inner.network(network_idx).flags.get() & format::NETWORK_FLAG_ANYCAST != 0
+        })
+    }
+    /// Iterate over all networks in the database.
+    ///
+    /// The networks are yielded in the order of the network tree, i.e.
+    /// ordered by their IPv6 address, with IPv4 networks at the position of
+    /// their IPv4-mapped IPv6 addresses `::ffff:0:0/96`. IPv4 networks are
+    /// returned as such, see [`Network::addrs`].
+    ///
+    /// ```
+    /// use libloc::TestDatabaseBuilder;
+    ///
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network("2001:db8::/32", 64496, "DE")
+    ///     .add_network("192.0.2.0/24", 64497, "FR")
+    ///     .add_network("::/8", 64498, "XX")
+    ///     .build();
+    ///
+    /// let prefixes: Vec<_> = locations.networks().map(|n| n.addrs().to_string()).collect();
+    /// assert_eq!(prefixes, ["::/8", "192.0.2.0/24", "2001:db8::/32"]);
+    /// ```
+    ///
+    /// The iterator is lazy, so stopping early doesn't walk the whole tree:
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let network = locations.networks().next().unwrap();
+    /// assert_eq!(network.addrs().to_string(), "2a07:1c44:5800::/40");
+    /// assert_eq!(network.asn(), 204867);
+    /// assert_eq!(locations.networks().count(), 1);
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    ///
+    /// Trees without networks, consisting of just the root node, and networks
+    /// at the root node or at the root of the IPv4 networks are handled:
+    ///
+    /// ```
+    /// use libloc::TestDatabaseBuilder;
+    ///
+    /// let locations = TestDatabaseBuilder::new().build();
+    /// assert_eq!(locations.network_node_count(), 1);
+    /// assert_eq!(locations.networks().count(), 0);
+    ///
+    /// let locations = TestDatabaseBuilder::new().add_network("::/0", 64496, "DE").build();
+    /// assert_eq!(locations.network_node_count(), 1);
+    /// let prefixes: Vec<_> = locations.networks().map(|n| n.addrs().to_string()).collect();
+    /// assert_eq!(prefixes, ["::/0"]);
+    ///
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network("0.0.0.0/0", 64496, "DE")
+    ///     .add_network("255.255.255.255/32", 64497, "FR")
+    ///     .build();
+    /// let prefixes: Vec<_> = locations.networks().map(|n| n.addrs().to_string()).collect();
+    /// assert_eq!(prefixes, ["0.0.0.0/0", "255.255.255.255/32"]);
+    /// ```
+    pub fn networks(&self) -> Networks<'_> {
+        Networks {
+            walk: TreeWalk::new(self.inner.get()),
         }
-        &self.countries[index]
     }
-    fn string(&self, str_ref: format::StrRef) -> &'a str {
-        let offset = str_ref.offset.get() as usize;
-        if offset > self.string_pool.len() {
-            panic!(
-                "corrupt libloc db: invalid str_ref: {} > {}",
-                offset,
-                self.string_pool.len(),
-            );
+    /// Iterate over all networks within a prefix.
+    ///
+    /// These are the networks equal to `net` or more specific than it, in
+    /// the same order as for [`Locations::networks`]. Less specific networks
+    /// containing `net` are not included, see
+    /// [`Locations::covering_networks`]. Host bits of `net` are ignored.
+    ///
+    /// Only the part of the network tree below `net` is walked.
+    ///
+    /// ```
+    /// use libloc::TestDatabaseBuilder;
+    ///
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network("192.0.0.0/8", 64496, "DE")
+    ///     .add_network("192.0.2.0/24", 64497, "DE")
+    ///     .add_network("192.0.2.128/25", 64498, "FR")
+    ///     .add_network("198.51.100.0/24", 64499, "FR")
+    ///     .build();
+    ///
+    /// let under = |net: &str| -> Vec<_> {
+    ///     locations
+    ///         .networks_under(net.parse().unwrap())
+    ///         .map(|n| n.addrs().to_string())
+    ///         .collect()
+    /// };
+    /// assert_eq!(under("192.0.2.0/23"), ["192.0.2.0/24", "192.0.2.128/25"]);
+    /// assert_eq!(under("192.0.2.0/24"), ["192.0.2.0/24", "192.0.2.128/25"]);
+    /// assert_eq!(under("192.0.2.128/25"), ["192.0.2.128/25"]);
+    /// assert!(under("192.0.2.0/25").is_empty());
+    /// assert_eq!(under("0.0.0.0/0").len(), 4);
+    /// assert!(under("2001:db8::/32").is_empty());
+    /// ```
+    pub fn networks_under(&self, net: IpNet) -> Networks<'_> {
+        let inner = self.inner.get();
+        let net = match net.trunc() {
+            IpNet::V4(net) => {
+                Ipv6Net::new(net.network().to_ipv6_mapped(), net.prefix_len() + 96).unwrap()
+            }
+            IpNet::V6(net) => net,
+        };
+        let len = net.prefix_len();
+        let bits = u128::from(net.network());
+        let node = if inner.network_nodes.is_empty() {
+            None
+        } else {
+            inner.find_network_node(0, bits.reverse_bits(), len.into())
+        };
+        let walk = match node {
+            Some(node) => TreeWalk::from_node(
+                inner,
+                node,
+                bits.checked_shr(128 - u32::from(len)).unwrap_or(0),
+                len,
+            ),
+            None => TreeWalk::empty(inner),
+        };
+        Networks { walk }
+    }
+    /// The fractions of the globally routable IPv4 and IPv6 address space
+    /// covered by networks of the database.
+    ///
+    /// For IPv4, the routable address space is the whole address space
+    /// except for the following reserved ranges:
+    ///
+    /// - `0.0.0.0/8` ("this network"),
+    /// - `10.0.0.0/8`, `172.16.0.0/12` and `192.168.0.0/16` (private use),
+    /// - `100.64.0.0/10` (shared address space),
+    /// - `127.0.0.0/8` (loopback),
+    /// - `169.254.0.0/16` (link local),
+    /// - `192.0.0.0/24` (IETF protocol assignments),
+    /// - `192.0.2.0/24`, `198.51.100.0/24` and `203.0.113.0/24`
+    ///   (documentation),
+    /// - `198.18.0.0/15` (benchmarking),
+    /// - `224.0.0.0/4` (multicast),
+    /// - `240.0.0.0/4` (reserved, including the broadcast address).
+    ///
+    /// For IPv6, it is the global unicast address space `2000::/3` except for
+    /// `2001::/23` (IETF protocol assignments), `2001:db8::/32` and
+    /// `3fff::/20` (documentation) and `2002::/16` (6to4).
+    ///
+    /// This walks the whole network tree.
+    ///
+    /// ```
+    /// use libloc::TestDatabaseBuilder;
+    ///
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network("0.0.0.0/0", 64496, "DE")
+    ///     .add_network("10.0.0.0/8", 64496, "DE")
+    ///     .add_network("2000::/4", 64496, "DE")
+    ///     .build();
+    ///
+    /// let (v4, v6) = locations.coverage_ratio();
+    /// assert_eq!(v4, 1.0);
+    /// assert!((v6 - 0.5).abs() < 1e-4);
+    ///
+    /// assert_eq!(TestDatabaseBuilder::new().build().coverage_ratio(), (0.0, 0.0));
+    /// ```
+    pub fn coverage_ratio(&self) -> (f64, f64) {
+        fn ratio(
+            networks: &[(u128, u8)],
+            universe: (u128, u8),
+            reserved: &[(u128, u8)],
+            width: u8,
+        ) -> f64 {
+            let routable = |(bits, len)| {
+                let reserved: u128 = reserved
+                    .iter()
+                    .map(|&r| prefix_overlap((bits, len), r, width))
+                    .sum();
+                prefix_overlap((bits, len), universe, width) - reserved
+            };
+            let covered: u128 = networks.iter().map(|&n| routable(n)).sum();
+            covered as f64 / routable(universe) as f64
+        }
+
+        // Only count the least specific networks, the others are contained
+        // in them. Networks are visited before the ones they contain.
+        let mut v4 = Vec::new();
+        let mut v6 = Vec::new();
+        for (bits, len, _) in TreeWalk::new(self.inner.get()) {
+            let (networks, bits, len, width) = match prefix(bits, len) {
+                IpNet::V4(net) => (
+                    &mut v4,
+                    u32::from(net.network()).into(),
+                    net.prefix_len(),
+                    32,
+                ),
+                IpNet::V6(net) => (&mut v6, u128::from(net.network()), net.prefix_len(), 128),
+            };
+            match networks.last() {
+                Some(&last) if prefix_overlap(last, (bits, len), width) != 0 => {}
+                _ => networks.push((bits, len)),
+            }
         }
-        let bytes = &self.string_pool[offset..];
-        let bytes = &bytes[..bytes
+        let reserved_v4: Vec<_> = RESERVED_V4
             .iter()
-            .copied()
-            .position(|b| b == 0)
-            .unwrap_or_else(|| {
-                panic!(
-                    "corrupt libloc db: missing null termination for str_ref: {}",
-                    offset,
-                );
-            })];
-        str::from_utf8(bytes).unwrap_or_else(|e| {
-            panic!(
-                "corrupt libloc db: invalid UTF-8 for str_ref: {}: {}",
-                offset, e,
-            )
+            .map(|&(addr, len)| (u32::from(addr).into(), len))
+            .collect();
+        let reserved_v6: Vec<_> = RESERVED_V6
+            .iter()
+            .map(|&(addr, len)| (u128::from(addr), len))
+            .collect();
+        (
+            ratio(&v4, (0, 0), &reserved_v4, 32),
+            ratio(
+                &v6,
+                (u128::from(GLOBAL_UNICAST_V6.0), GLOBAL_UNICAST_V6.1),
+                &reserved_v6,
+                128,
+            ),
+        )
+    }
+    /// Iterate over all networks of an [AS] (autonomous system).
+    ///
+    /// The networks are yielded in the order of [`Locations::networks`].
+    /// Nothing is yielded for AS0, which marks networks with an unknown AS.
+    ///
+    /// This walks the whole network tree, so it takes time proportional to
+    /// the size of the database, even if the AS has few networks.
+    ///
+    /// ```
+    /// use libloc::TestDatabaseBuilder;
+    ///
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network("192.0.2.0/24", 64496, "DE")
+    ///     .add_network("198.51.100.0/24", 64497, "DE")
+    ///     .add_network("2001:db8::/32", 64496, "DE")
+    ///     .add_network("203.0.113.0/24", 0, "DE")
+    ///     .build();
+    ///
+    /// let prefixes: Vec<_> = locations.networks_for_asn(64496).map(|n| n.addrs()).collect();
+    /// assert_eq!(prefixes, ["192.0.2.0/24".parse().unwrap(), "2001:db8::/32".parse().unwrap()]);
+    /// assert!(prefixes[0].network().is_ipv4());
+    /// assert_eq!(locations.networks_for_asn(0).count(), 0);
+    /// ```
+    ///
+    /// [AS]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
+    pub fn networks_for_asn(&self, asn: u32) -> impl Iterator<Item = Network<'_>> {
+        let inner = self.inner.get();
+        TreeWalk::new(inner)
+            .filter(move |&(_, _, network_idx)| {
+                asn != 0 && inner.network(network_idx).asn.get() == asn
+            })
+            .map(move |(bits, len, network_idx)| inner.network_at(bits, len, network_idx))
+    }
+    /// Iterate over all networks that have all of the given flags set.
+    ///
+    /// The networks are yielded in the order of [`Locations::networks`].
+    /// See also the shortcuts [`Locations::drop_networks`],
+    /// [`Locations::anonymous_proxy_networks`],
+    /// [`Locations::satellite_networks`] and [`Locations::anycast_networks`].
+    ///
+    /// This walks the whole network tree.
+    ///
+    /// ```
+    /// use libloc::{NetworkFlags, TestDatabaseBuilder};
+    ///
+    /// let flags = NetworkFlags::ANYCAST | NetworkFlags::DROP;
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network_with_flags("192.0.2.0/24", 64496, "DE", flags)
+    ///     .add_network_with_flags("198.51.100.0/24", 64496, "DE", NetworkFlags::DROP)
+    ///     .add_network("203.0.113.0/24", 64496, "DE")
+    ///     .build();
+    ///
+    /// let prefixes: Vec<_> = locations.networks_with_flags(flags).map(|n| n.addrs()).collect();
+    /// assert_eq!(prefixes, ["192.0.2.0/24".parse().unwrap()]);
+    ///
+    /// assert_eq!(locations.drop_networks().count(), 2);
+    /// assert!(locations.drop_networks().all(|n| n.is_drop()));
+    /// assert_eq!(locations.anycast_networks().count(), 1);
+    /// assert_eq!(locations.anonymous_proxy_networks().count(), 0);
+    /// assert_eq!(locations.satellite_networks().count(), 0);
+    /// ```
+    pub fn networks_with_flags(&self, flags: NetworkFlags) -> impl Iterator<Item = Network<'_>> {
+        let inner = self.inner.get();
+        TreeWalk::new(inner)
+            .filter(move |&(_, _, network_idx)| {
+                NetworkFlags::from_bits(inner.network(network_idx).flags.get()).contains(flags)
+            })
+            .map(move |(bits, len, network_idx)| inner.network_at(bits, len, network_idx))
+    }
+    /// Iterate over all networks on the [Spamhaus DROP] list.
+    ///
+    /// See [`Locations::networks_with_flags`] and [`Network::is_drop`].
+    ///
+    /// [Spamhaus DROP]: https://www.spamhaus.org/blocklists/do-not-route-or-peer/
+    pub fn drop_networks(&self) -> impl Iterator<Item = Network<'_>> {
+        self.networks_with_flags(NetworkFlags::DROP)
+    }
+    /// Iterate over all networks of anonymous proxies.
+    ///
+    /// See [`Locations::networks_with_flags`] and
+    /// [`Network::is_anonymous_proxy`].
+    pub fn anonymous_proxy_networks(&self) -> impl Iterator<Item = Network<'_>> {
+        self.networks_with_flags(NetworkFlags::ANONYMOUS_PROXY)
+    }
+    /// Iterate over all networks of satellite providers.
+    ///
+    /// See [`Locations::networks_with_flags`] and
+    /// [`Network::is_satellite_provider`].
+    pub fn satellite_networks(&self) -> impl Iterator<Item = Network<'_>> {
+        self.networks_with_flags(NetworkFlags::SATELLITE_PROVIDER)
+    }
+    /// Iterate over all [anycast] networks.
+    ///
+    /// See [`Locations::networks_with_flags`] and [`Network::is_anycast`].
+    ///
+    /// [anycast]: https://en.wikipedia.org/wiki/Anycast
+    pub fn anycast_networks(&self) -> impl Iterator<Item = Network<'_>> {
+        self.networks_with_flags(NetworkFlags::ANYCAST)
+    }
+    /// Iterate over all networks in the database together with their
+    /// countries.
+    ///
+    /// The country is `None` if it is unknown (`"XX"`) or not in the
+    /// database, like for [`Locations::country_of`]. The networks are yielded
+    /// in the order of [`Locations::networks`]. The country table is indexed
+    /// up front, so finding the country is cheap for every network.
+    ///
+    /// ```
+    /// use libloc::TestDatabaseBuilder;
+    ///
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network("192.0.2.0/24", 64496, "DE")
+    ///     .add_network("198.51.100.0/24", 64496, "XX")
+    ///     .add_network("203.0.113.0/24", 64496, "FR")
+    ///     .add_country("DE", "EU", "Germany")
+    ///     .build();
+    ///
+    /// let countries: Vec<_> = locations
+    ///     .networks_with_country()
+    ///     .map(|(_, country)| country.map(|c| c.name()))
+    ///     .collect();
+    /// assert_eq!(countries, [Some("Germany"), None, None]);
+    /// ```
+    pub fn networks_with_country(
+        &self,
+    ) -> impl Iterator<Item = (Network<'_>, Option<Country<'_>>)> {
+        let inner = self.inner.get();
+        let countries: HashMap<[u8; 2], u32> = inner
+            .countries
+            .iter()
+            .zip(0..)
+            .map(|(country, index)| (country.code, index))
+            .collect();
+        self.networks().map(move |network| {
+            let country = match network.inner.country_code {
+                b"XX" => None,
+                code => countries
+                    .get(code)
+                    .map(|&index| Country::from(inner, inner.country(index))),
+            };
+            (network, country)
         })
     }
-}
-
-trait ByteSliceExt {
-    fn get_range(&self, range: format::FileRange) -> Option<&[u8]>;
-    fn get_typed_range<T: FromBytes>(&self, range: format::FileRange) -> Option<&[T]>;
-}
-impl<'a> ByteSliceExt for [u8] {
-    fn get_range(&self, range: format::FileRange) -> Option<&[u8]> {
-        let start = range.offset.get();
-        let end = range.offset.get().checked_add(range.length.get())?;
-        self.get(start as usize..end as usize)
+    /// Iterate over the prefix, ASN and country code of all networks in the
+    /// database.
+    ///
+    /// This is cheaper than [`Locations::networks`] as it doesn't look at
+    /// anything else. The order is the same.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let summary: Vec<_> = locations.summary().collect();
+    /// assert_eq!(summary, [("2a07:1c44:5800::/40".parse().unwrap(), 204867, *b"DE")]);
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn summary(&self) -> impl Iterator<Item = (IpNet, u32, [u8; 2])> + '_ {
+        let inner = self.inner.get();
+        TreeWalk::new(inner).map(move |(bits, len, network_idx)| {
+            let network = inner.network(network_idx);
+            (prefix(bits, len), network.asn.get(), network.country_code)
+        })
+    }
+    /// Find networks whose continent disagrees with the other networks of
+    /// their AS.
+    ///
+    /// For every AS with networks on more than one continent, the networks
+    /// not on the continent with the most networks of that AS are yielded.
+    /// Ties are broken in favor of the alphabetically first continent code.
+    /// Networks without a known AS (AS0) or country (`"XX"` or not in the
+    /// database) are ignored.
+    ///
+    /// This can help spotting mislabeled networks. It walks all networks
+    /// twice and allocates memory proportional to the number of ASs.
+    ///
+    /// ```
+    /// use libloc::TestDatabaseBuilder;
+    ///
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network("192.0.2.0/24", 64496, "DE")
+    ///     .add_network("198.51.100.0/24", 64496, "FR")
+    ///     .add_network("203.0.113.0/24", 64496, "US")
+    ///     .add_network("2001:db8::/32", 64497, "US")
+    ///     .add_country("DE", "EU", "Germany")
+    ///     .add_country("FR", "EU", "France")
+    ///     .add_country("US", "NA", "United States of America")
+    ///     .build();
+    ///
+    /// let outliers: Vec<_> = locations.geo_outliers().map(|n| n.addrs().to_string()).collect();
+    /// assert_eq!(outliers, ["203.0.113.0/24"]);
+    /// ```
+    pub fn geo_outliers(&self) -> impl Iterator<Item = Network<'_>> + '_ {
+        let inner = self.inner.get();
+        let continents: HashMap<[u8; 2], [u8; 2]> = inner
+            .countries
+            .iter()
+            .map(|c| (c.code, c.continent_code))
+            .collect();
+        let continent = move |network: &Network| -> Option<[u8; 2]> {
+            if network.inner.asn == 0 {
+                return None;
+            }
+            continents.get(network.inner.country_code).copied()
+        };
+
+        let mut counts: HashMap<u32, HashMap<[u8; 2], usize>> = HashMap::new();
+        for network in self.networks() {
+            if let Some(continent) = continent(&network) {
+                *counts
+                    .entry(network.inner.asn)
+                    .or_default()
+                    .entry(continent)
+                    .or_default() += 1;
+            }
+        }
+        let majorities: HashMap<u32, [u8; 2]> = counts
+            .into_iter()
+            .filter(|(_, counts)| counts.len() > 1)
+            .map(|(asn, counts)| {
+                let (majority, _) = counts
+                    .into_iter()
+                    .max_by(|(c1, n1), (c2, n2)| n1.cmp(n2).then(c2.cmp(c1)))
+                    .unwrap();
+                (asn, majority)
+            })
+            .collect();
+
+        self.networks().filter(move |network| {
+            match (majorities.get(&network.inner.asn), continent(network)) {
+                (Some(majority), Some(continent)) => *majority != continent,
+                _ => false,
+            }
+        })
     }
-    fn get_typed_range<T: FromBytes>(&self, range: format::FileRange) -> Option<&[T]> {
-        self.get_range(range).and_then(T::slice_from)
+    /// Create a [`Classifier`] for looking up many addresses in a row.
+    ///
+    /// See [`Classifier`] for when this is faster than [`Locations::lookup`].
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let mut classifier = locations.classifier();
+    /// for addr in ["2a07:1c44:5800::1", "2a07:1c44:5800::2", "127.0.0.1"] {
+    ///     let addr = addr.parse().unwrap();
+    ///     assert_eq!(
+    ///         classifier.classify(addr).map(|n| n.asn()),
+    ///         locations.lookup(addr).map(|n| n.asn()),
+    ///     );
+    /// }
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn classifier(&self) -> Classifier<'_> {
+        Classifier {
+            inner: self.inner.get(),
+            last_v4: None,
+            last_v6: None,
+        }
     }
-}
-
-impl Locations {
-    /// Open a database in libloc format.
+    /// Look up a country by its [ISO 3166-1 alpha-2] code.
     ///
-    /// # Safety
+    /// The code is matched case-insensitively and surrounding whitespace is
+    /// ignored, so user input can be passed as is. Codes that aren't two
+    /// ASCII letters never match.
     ///
-    /// This memory-maps the database. This is efficient, but you must make
-    /// sure that it's not modified during the usage. See the safety discussion
-    /// of the `Mmap` struct of [`memmap2`](https://docs.rs/memmap2/).
+    /// [ISO 3166-1 alpha-2]: https://en.wikipedia.org/wiki/ISO_3166-1_alpha-2
     ///
-    /// # Errors
+    /// ```
+    /// use libloc::Locations;
     ///
-    /// Errors can occur when the specified database file cannot be opened for
-    /// reading (e.g. because it does not exist), this is communicated via the
-    /// [`OpenError::Open`] variant.
+    /// let locations = Locations::open("example-location.db")?;
+    /// assert_eq!(locations.country("DE").unwrap().name(), "Germany");
+    /// assert_eq!(locations.country("de").unwrap().code(), "DE");
+    /// assert_eq!(locations.country(" DE ").unwrap().code(), "DE");
+    /// assert!(matches!(locations.country("d1"), None));
+    /// assert!(matches!(locations.country("XX"), None));
     ///
-    /// Additionally, if the opened file is not in a format valid for this
-    /// crate, it is likely that the [`OpenError::InvalidMagic`] variant is
-    /// returned.
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn country(&self, code: &str) -> Option<Country<'_>> {
+        let inner = self.inner.get();
+        Some(Country::from(
+            inner,
+            inner.country(inner.find_country(code)?),
+        ))
+    }
+    /// Look up a country by its [ISO 3166-1 alpha-2] code, returning an error
+    /// instead of panicking if the database is corrupt.
     ///
-    /// If the database is obviously corrupt, e.g. truncated, other errors
-    /// might be returned.
+    /// Like [`Locations::country`], this returns `Ok(None)` if there is no
+    /// such country and ignores case and surrounding whitespace. The codes
+    /// and name of the returned country are checked to be valid UTF-8.
     ///
-    /// # Examples
+    /// [ISO 3166-1 alpha-2]: https://en.wikipedia.org/wiki/ISO_3166-1_alpha-2
     ///
     /// ```
     /// use libloc::Locations;
     ///
     /// let locations = Locations::open("example-location.db")?;
+    /// assert_eq!(locations.try_country("DE").unwrap().unwrap().name(), "Germany");
+    /// assert!(matches!(locations.try_country("XX"), Ok(None)));
     ///
-    /// // IO errors while opening the file are reported via the `Open(_)`
-    /// // variant.
-    /// assert!(matches!(Locations::open("non-existing"), Err(libloc::OpenError::Open(_))));
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn try_country(&self, code: &str) -> Result<Option<Country<'_>>, LookupError> {
+        self.inner.get().try_country_by_code(code)
+    }
+    /// Iterate over all countries in the database.
     ///
-    /// // Files that are not in the required format are likely to give the
-    /// // `InvalidMagic` error.
-    /// assert!(matches!(Locations::open("Cargo.toml"), Err(libloc::OpenError::InvalidMagic)));
+    /// The countries are ordered by their code.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let countries = locations.countries();
+    /// assert_eq!(countries.len(), 1);
+    /// for country in countries {
+    ///     assert_eq!(country.code(), "DE");
+    ///     assert_eq!(country.continent_code(), "EU");
+    ///     assert_eq!(country.name(), "Germany");
+    /// }
     ///
     /// # Ok::<(), libloc::OpenError>(())
     /// ```
-    pub fn open<P: AsRef<Path>>(path: P) -> Result<Locations, OpenError> {
-        fn inner(path: &Path) -> Result<Locations, OpenError> {
-            use self::OpenError as Error;
-            let file = File::open(path).map_err(Error::Open)?;
-            let mmap = unsafe { Mmap::map(&file) }.map_err(Error::Mmap)?;
-
-            if !mmap.starts_with(&format::MAGIC) {
-                return Err(Error::InvalidMagic);
-            }
-
-            // This is just an optimization, ignore errors.
-            #[cfg(unix)]
-            let _ = mmap.advise(memmap2::Advice::Random);
-
-            let inner = Yoke::try_attach_to_cart(mmap, |mmap| -> Result<_, Error> {
-                let header =
-                    format::Header::ref_from_prefix(&mmap).ok_or(Error::CouldntReadHeader)?;
-                if header.version != format::VERSION {
-                    return Err(Error::UnsupportedVersion(header.version));
-                }
-
-                let mut inner = LocationsInner {
-                    as_: mmap
-                        .get_typed_range(header.as_)
-                        .ok_or(Error::InvalidAsRange)?,
-                    networks: mmap
-                        .get_typed_range(header.networks)
-                        .ok_or(Error::InvalidNetworkRange)?,
-                    network_nodes: mmap
-                        .get_typed_range(header.network_nodes)
-                        .ok_or(Error::InvalidNetworkNodeRange)?,
-                    countries: mmap
-                        .get_typed_range(header.countries)
-                        .ok_or(Error::InvalidCountryRange)?,
-                    string_pool: mmap
-                        .get_range(header.string_pool)
-                        .ok_or(Error::InvalidStringPoolRange)?,
-
-                    header,
-
-                    ipv4_network_node: Some(u32::MAX), // invalid value
-                };
-                let ipv4_mapped_prefix = u128::from(Ipv4Addr::from(0).to_ipv6_mapped());
-                inner.ipv4_network_node =
-                    inner.find_network_node(0, ipv4_mapped_prefix.reverse_bits(), 96);
-                Ok(inner)
-            })?;
-            Ok(Locations { inner })
+    pub fn countries(&self) -> Countries<'_> {
+        let inner = self.inner.get();
+        Countries {
+            inner,
+            iter: inner.countries.iter(),
         }
-        inner(path.as_ref())
     }
-    /// The database creation time.
+    /// Iterate over the countries of a continent.
+    ///
+    /// See [`Country::continent_code`] for the continent codes. Like for
+    /// [`Locations::country`], case and surrounding whitespace are ignored.
+    /// Other codes yield no countries. The countries are ordered by their
+    /// code.
     ///
     /// ```
     /// use libloc::Locations;
     ///
     /// let locations = Locations::open("example-location.db")?;
-    /// assert_eq!(locations.created_at().to_string(), "2024-02-06 22:30:29 UTC");
+    /// let codes: Vec<_> = locations.countries_in_continent("EU").map(|c| c.code()).collect();
+    /// assert_eq!(codes, ["DE"]);
+    /// assert_eq!(locations.countries_in_continent("eu").count(), 1);
+    /// assert_eq!(locations.countries_in_continent("AS").count(), 0);
+    /// assert_eq!(locations.countries_in_continent("XX").count(), 0);
     ///
     /// # Ok::<(), libloc::OpenError>(())
     /// ```
-    #[cfg(feature = "time")]
-    pub fn created_at(&self) -> chrono::DateTime<chrono::offset::Utc> {
-        let inner = self.inner.get();
-        let created_at = inner.header.created_at.get();
-        chrono::DateTime::from_timestamp(
-            created_at.try_into().unwrap_or_else(|_| {
-                panic!(
-                    "corrupt libloc db: invalid created_at header: {}",
-                    created_at,
-                )
-            }),
-            0,
-        )
-        .unwrap_or_else(|| {
-            panic!(
-                "corrupt libloc db: invalid created_at header: {}",
-                created_at,
-            )
-        })
+    pub fn countries_in_continent(
+        &self,
+        continent_code: &str,
+    ) -> impl Iterator<Item = Country<'_>> {
+        let code = continent_code.trim().to_ascii_uppercase();
+        let code = CONTINENT_CODES
+            .iter()
+            .find(|&&c| c == code)
+            .map(|c| [c.as_bytes()[0], c.as_bytes()[1]]);
+        self.countries()
+            .filter(move |country| Some(*country.continent_code) == code)
     }
-    /// The vendor of the database.
+    /// Whether a country with the given [ISO 3166-1 alpha-2] code is in the
+    /// database.
+    ///
+    /// This is cheaper than checking the result of [`Locations::country`], as
+    /// it doesn't resolve the country's name. Like [`Locations::country`],
+    /// this ignores case and surrounding whitespace.
+    ///
+    /// [ISO 3166-1 alpha-2]: https://en.wikipedia.org/wiki/ISO_3166-1_alpha-2
     ///
     /// ```
     /// use libloc::Locations;
     ///
     /// let locations = Locations::open("example-location.db")?;
-    /// assert_eq!(locations.vendor(), "IPFire Project");
+    /// assert!(locations.has_country("DE"));
+    /// assert!(!locations.has_country("XX"));
+    /// assert!(!locations.has_country("DEU"));
     ///
     /// # Ok::<(), libloc::OpenError>(())
     /// ```
-    pub fn vendor(&self) -> &str {
+    pub fn has_country(&self, code: &str) -> bool {
+        self.inner.get().find_country(code).is_some()
+    }
+    /// The smallest set of prefixes covering exactly the addresses in a
+    /// country.
+    ///
+    /// An address is in the country if [`Locations::lookup`] returns a
+    /// network with the given country code for it. In particular, more
+    /// specific networks of other countries are cut out of the networks of
+    /// the country. Adjacent prefixes are merged, but never in a way that
+    /// adds addresses outside of the country. IPv4 prefixes come first, both
+    /// families are sorted.
+    ///
+    /// Like [`Locations::country`], this ignores case and surrounding
+    /// whitespace of the code. Codes that aren't two letters yield no
+    /// prefixes.
+    ///
+    /// This walks the whole network tree.
+    ///
+    /// ```
+    /// use libloc::TestDatabaseBuilder;
+    ///
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network("10.0.0.0/8", 64496, "DE")
+    ///     .add_network("10.128.0.0/9", 64497, "FR")
+    ///     .add_network("11.0.0.0/8", 64496, "DE")
+    ///     .add_network("12.0.0.0/8", 64496, "DE")
+    ///     .add_network("2001:db8::/33", 64496, "DE")
+    ///     .add_network("2001:db8:8000::/33", 64496, "DE")
+    ///     .build();
+    ///
+    /// let prefixes: Vec<_> = locations
+    ///     .aggregated_country("DE")
+    ///     .iter()
+    ///     .map(|net| net.to_string())
+    ///     .collect();
+    /// assert_eq!(prefixes, ["10.0.0.0/9", "11.0.0.0/8", "12.0.0.0/8", "2001:db8::/32"]);
+    /// assert_eq!(locations.aggregated_country(" de "), locations.aggregated_country("DE"));
+    /// assert!(locations.aggregated_country("DEU").is_empty());
+    ///
+    /// // IPv6 networks don't extend into the IPv4 address space, just like
+    /// // for `Locations::lookup`.
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network("::/1", 64496, "DE")
+    ///     .add_network("192.0.2.0/24", 64497, "FR")
+    ///     .build();
+    /// assert!(locations.lookup("198.51.100.1".parse().unwrap()).is_none());
+    /// assert!(locations.aggregated_country("DE").iter().all(|net| net.network().is_ipv6()));
+    /// ```
+    pub fn aggregated_country(&self, code: &str) -> Vec<IpNet> {
+        let prefixes: Vec<IpNet> = self
+            .country_pieces(code)
+            .into_iter()
+            .map(|(net, _)| net)
+            .collect();
+        IpNet::aggregate(&prefixes)
+    }
+    /// The prefixes of a country's networks, merging adjacent ones with the
+    /// same ASN and flags.
+    ///
+    /// Like [`Locations::aggregated_country`], but prefixes are only merged
+    /// if the networks they come from share the ASN and flags, so every
+    /// returned prefix has uniform attributes. Both families are sorted, IPv4
+    /// prefixes come first.
+    ///
+    /// This walks the whole network tree.
+    ///
+    /// ```
+    /// use libloc::{NetworkFlags, TestDatabaseBuilder};
+    ///
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network("192.0.2.0/25", 64496, "DE")
+    ///     .add_network("192.0.2.128/25", 64496, "DE")
+    ///     .add_network("198.51.100.0/25", 64496, "DE")
+    ///     .add_network("198.51.100.128/25", 64497, "DE")
+    ///     .add_network("203.0.113.0/25", 64496, "DE")
+    ///     .add_network_with_flags("203.0.113.128/25", 64496, "DE", NetworkFlags::ANYCAST)
+    ///     .build();
+    ///
+    /// let prefixes: Vec<_> = locations
+    ///     .aggregate_networks_for_country("DE")
+    ///     .iter()
+    ///     .map(|net| net.to_string())
+    ///     .collect();
+    /// assert_eq!(
+    ///     prefixes,
+    ///     [
+    ///         "192.0.2.0/24",
+    ///         "198.51.100.0/25",
+    ///         "198.51.100.128/25",
+    ///         "203.0.113.0/25",
+    ///         "203.0.113.128/25",
+    ///     ],
+    /// );
+    /// ```
+    pub fn aggregate_networks_for_country(&self, code: &str) -> Vec<IpNet> {
         let inner = self.inner.get();
-        inner.string(inner.header.vendor)
+        let mut groups: HashMap<(u32, u16), Vec<IpNet>> = HashMap::new();
+        for (net, network_idx) in self.country_pieces(code) {
+            let network = inner.network(network_idx);
+            groups
+                .entry((network.asn.get(), network.flags.get()))
+                .or_default()
+                .push(net);
+        }
+        let mut result: Vec<IpNet> = groups.values().flat_map(IpNet::aggregate).collect();
+        result.sort();
+        result
     }
-    /// The description of the database.
+    /// The disjoint prefixes whose addresses are in the country `code`,
+    /// together with the index of the network they belong to.
+    ///
+    /// More specific networks of other countries are cut out.
+    fn country_pieces(&self, code: &str) -> Vec<(IpNet, u32)> {
+        let inner = self.inner.get();
+        let code = match normalize_country_code(code) {
+            Some(code) => code,
+            None => return Vec::new(),
+        };
+        let mut pieces = Vec::new();
+        // (node, bits, depth, the network the node's addresses are in unless
+        // a more specific network says otherwise, if it's in the country)
+        let mut stack = Vec::new();
+        if !inner.network_nodes.is_empty() {
+            stack.push((0, 0u128, 0u8, None));
+        }
+        while let Some((index, bits, depth, inherited)) = stack.pop() {
+            let node = inner.network_node(index);
+            let in_country = match node.network() {
+                Some(network_idx) => {
+                    Some(network_idx).filter(|&i| inner.network(i).country_code == code)
+                }
+                None => inherited,
+            };
+            let children = [node.children[0].get(), node.children[1].get()];
+            if children == [0, 0] {
+                if let Some(network_idx) = in_country {
+                    pieces.push((prefix(bits, depth), network_idx));
+                }
+                continue;
+            }
+            for (bit, &child) in children.iter().enumerate() {
+                let bits = bits << 1 | bit as u128;
+                // IPv4 lookups start at `::ffff:0:0/96`, networks above it
+                // don't cover IPv4 addresses.
+                let in_country = match (bits, depth + 1) {
+                    (0xffff, 96) => None,
+                    _ => in_country,
+                };
+                if child != 0 {
+                    stack.push((child, bits, depth + 1, in_country));
+                } else if let Some(network_idx) = in_country {
+                    pieces.push((prefix(bits, depth + 1), network_idx));
+                }
+            }
+        }
+        pieces
+    }
+    /// Look up the country of the network containing an IP address.
+    ///
+    /// Returns `None` if the address is not in any network, or if the
+    /// network's country is unknown (`"XX"`) or not in the database.
     ///
     /// ```
     /// use libloc::Locations;
     ///
     /// let locations = Locations::open("example-location.db")?;
-    /// assert_eq!(locations.description(), "This is a geo location database");
+    /// let country = locations.country_of("2a07:1c44:5800::1".parse().unwrap()).unwrap();
+    /// assert_eq!(country.name(), "Germany");
+    /// assert!(matches!(locations.country_of("127.0.0.1".parse().unwrap()), None));
     ///
     /// # Ok::<(), libloc::OpenError>(())
     /// ```
-    pub fn description(&self) -> &str {
+    pub fn country_of(&self, addr: IpAddr) -> Option<Country<'_>> {
         let inner = self.inner.get();
-        inner.string(inner.header.description)
+        inner.country_of(&inner.lookup(addr)?.inner)
     }
-    /// The license of the database.
+    /// Look up the network containing an IP address, together with its
+    /// [AS] and country.
+    ///
+    /// Returns `None` if the address is not in any network. The AS and
+    /// country are resolved like in [`Locations::as_of`] and
+    /// [`Locations::country_of`]. With the `serde` feature, the result can be
+    /// serialized as `network`, `as` and `country`.
     ///
     /// ```
     /// use libloc::Locations;
     ///
     /// let locations = Locations::open("example-location.db")?;
-    /// assert_eq!(locations.license(), "CC");
+    /// let description = locations.describe("2a07:1c44:5800::1".parse().unwrap()).unwrap();
+    /// assert_eq!(description.network().addrs().to_string(), "2a07:1c44:5800::/40");
+    /// assert_eq!(description.as_().unwrap().name(), "Lightning Wire Labs GmbH");
+    /// assert_eq!(description.country().unwrap().name(), "Germany");
+    /// assert!(locations.describe("127.0.0.1".parse().unwrap()).is_none());
     ///
     /// # Ok::<(), libloc::OpenError>(())
     /// ```
-    pub fn license(&self) -> &str {
+    ///
+    /// [AS]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
+    pub fn describe(&self, addr: IpAddr) -> Option<Description<'_>> {
         let inner = self.inner.get();
-        inner.string(inner.header.license)
+        let network = inner.lookup(addr)?;
+        Some(Description {
+            as_: inner.as_of(&network.inner),
+            country: inner.country_of(&network.inner),
+            network,
+        })
     }
-    /// Look up an [AS] (autonomous system) by its [ASN] (number).
+    /// Look up the [AS] of the network containing an IP address.
     ///
-    /// Returns `None` if it does not appear in the database.
+    /// This is the same as [`Locations::as_of`], named to go along with
+    /// [`Locations::lookup`].
     ///
     /// ```
     /// use libloc::Locations;
     ///
     /// let locations = Locations::open("example-location.db")?;
-    /// assert_eq!(locations.as_(204867).unwrap().name(), "Lightning Wire Labs GmbH");
-    /// assert!(matches!(locations.as_(0), None));
+    /// let as_ = locations.lookup_as("2a07:1c44:5800::1".parse().unwrap()).unwrap();
+    /// assert_eq!(as_.name(), "Lightning Wire Labs GmbH");
+    /// assert!(matches!(locations.lookup_as("127.0.0.1".parse().unwrap()), None));
     ///
     /// # Ok::<(), libloc::OpenError>(())
     /// ```
     ///
     /// [AS]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
-    /// [ASN]: https://en.wikipedia.org/wiki/Autonomous_system_(Internet)
-    pub fn as_(&self, asn: u32) -> Option<As<'_>> {
-        let inner = self.inner.get();
-
-        // The ASs are stored sorted by ASN in the database, so we can use a
-        // binary search to find a particular one.
-        let index = inner
-            .as_
-            .binary_search_by_key(&asn, |as_| as_.id.get())
-            .ok()?;
-        Some(As::from(inner, inner.as_(index.try_into().unwrap())))
+    pub fn lookup_as(&self, addr: IpAddr) -> Option<As<'_>> {
+        self.as_of(addr)
     }
-    /// Look up network information for an IP address.
+    /// Look up the country of the network containing an IP address.
+    ///
+    /// This is the same as [`Locations::country_of`], named to go along with
+    /// [`Locations::lookup`].
     ///
     /// ```
     /// use libloc::Locations;
     ///
     /// let locations = Locations::open("example-location.db")?;
-    /// assert_eq!(locations.lookup("2a07:1c44:5800::1".parse().unwrap()).unwrap().asn(), 204867);
-    /// assert!(matches!(locations.lookup("127.0.0.1".parse().unwrap()), None));
+    /// let country = locations.lookup_country("2a07:1c44:5800::1".parse().unwrap()).unwrap();
+    /// assert_eq!(country.name(), "Germany");
+    /// assert!(matches!(locations.lookup_country("127.0.0.1".parse().unwrap()), None));
     ///
     /// # Ok::<(), libloc::OpenError>(())
     /// ```
-    pub fn lookup(&self, addr: IpAddr) -> Option<Network<'_>> {
-        match addr {
-            IpAddr::V4(addr) => self.lookup_v4(addr).map(Into::into),
-            IpAddr::V6(addr) => self.lookup_v6(addr).map(Into::into),
-        }
+    pub fn lookup_country(&self, addr: IpAddr) -> Option<Country<'_>> {
+        self.country_of(addr)
     }
-    /// Look up network information for an IPv4 address.
+    /// Look up the continent code of the country of the network containing an
+    /// IP address.
     ///
-    /// See [`Locations::lookup`].
-    pub fn lookup_v4(&self, addr: Ipv4Addr) -> Option<NetworkV4<'_>> {
+    /// Returns `None` in the same cases as [`Locations::country_of`]. See
+    /// [`Country::continent_code`] for the possible continent codes.
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// assert_eq!(locations.continent_of("2a07:1c44:5800::1".parse().unwrap()), Some("EU"));
+    /// assert_eq!(locations.continent_of("127.0.0.1".parse().unwrap()), None);
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn continent_of(&self, addr: IpAddr) -> Option<&str> {
         let inner = self.inner.get();
-
-        let (num_bits, network_idx) = inner.find_network(
-            inner.ipv4_network_node?,
-            u32::from(addr).reverse_bits().into(),
-            32,
-        )?;
-        let addrs = Ipv4Net::new(addr, num_bits).unwrap().trunc();
-
-        Some(NetworkV4 {
-            inner: NetworkInner::from(inner, inner.network(network_idx)),
-            addrs,
-        })
+        Some(
+            inner
+                .country_of(&inner.lookup(addr)?.inner)?
+                .continent_code(),
+        )
     }
-    /// Look up network information for an IPv6 address.
+    /// Look up the country codes of the networks containing many IP
+    /// addresses.
     ///
-    /// See [`Locations::lookup`].
-    pub fn lookup_v6(&self, addr: Ipv6Addr) -> Option<NetworkV6<'_>> {
-        let inner = self.inner.get();
-
-        let (num_bits, network_idx) =
-            inner.find_network(0, u128::from(addr).reverse_bits(), 128)?;
-        let addrs = Ipv6Net::new(addr, num_bits).unwrap().trunc();
-
-        Some(NetworkV6 {
-            inner: NetworkInner::from(inner, inner.network(network_idx)),
-            addrs,
-        })
+    /// The results are in the order of `addrs`. Like for
+    /// [`Locations::country_of`], a result is `None` if the address is not in
+    /// any network or if the network's country is unknown (`"XX"`). Unlike
+    /// it, the country codes are returned even if the country is not in the
+    /// database.
+    ///
+    /// The addresses are looked up like by [`Locations::lookup_many`].
+    ///
+    /// ```
+    /// use libloc::Locations;
+    ///
+    /// let locations = Locations::open("example-location.db")?;
+    /// let addrs = ["2a07:1c44:5800::1".parse().unwrap(), "127.0.0.1".parse().unwrap()];
+    /// assert_eq!(locations.countries_of(&addrs), [Some("DE"), None]);
+    ///
+    /// # Ok::<(), libloc::OpenError>(())
+    /// ```
+    pub fn countries_of(&self, addrs: &[IpAddr]) -> Vec<Option<&str>> {
+        self.lookup_many(addrs)
+            .into_iter()
+            .map(|network| network?.country())
+            .collect()
     }
-    /// Look up a country by its [ISO 3166-1 alpha-2] code.
+    /// Look up network information for many IP addresses.
     ///
-    /// [ISO 3166-1 alpha-2]: https://en.wikipedia.org/wiki/ISO_3166-1_alpha-2
+    /// The results are in the order of `addrs`, each being what
+    /// [`Locations::lookup`] returns for the address.
+    ///
+    /// The addresses are looked up in sorted order with a [`Classifier`], so
+    /// that nearby addresses share most of the tree walk and touch the same
+    /// network nodes. For large batches of addresses, this is faster than
+    /// calling [`Locations::lookup`] for each of them.
+    ///
+    /// ```
+    /// use libloc::TestDatabaseBuilder;
+    ///
+    /// let locations = TestDatabaseBuilder::new()
+    ///     .add_network("192.0.2.0/24", 64496, "DE")
+    ///     .add_network("2001:db8::/32", 64497, "FR")
+    ///     .build();
+    ///
+    /// let addrs = [
+    ///     "2001:db8::1".parse().unwrap(),
+    ///     "192.0.2.1".parse().unwrap(),
+    ///     "127.0.0.1".parse().unwrap(),
+    ///     "192.0.2.2".parse().unwrap(),
+    /// ];
+    /// let asns: Vec<_> = locations
+    ///     .lookup_many(&addrs)
+    ///     .iter()
+    ///     .map(|network| network.as_ref().map(|n| n.asn()))
+    ///     .collect();
+    /// assert_eq!(asns, [Some(64497), Some(64496), None, Some(64496)]);
+    /// ```
+    pub fn lookup_many(&self, addrs: &[IpAddr]) -> Vec<Option<Network<'_>>> {
+        let mut order: Vec<usize> = (0..addrs.len()).collect();
+        order.sort_unstable_by_key(|&i| addrs[i]);
+        let mut classifier = self.classifier();
+        let mut result: Vec<Option<Network<'_>>> = (0..addrs.len()).map(|_| None).collect();
+        for i in order {
+            result[i] = classifier.classify(addrs[i]);
+        }
+        result
+    }
+    /// Look up network information for many IP addresses in parallel.
+    ///
+    /// The addresses are split into chunks that are looked up on the [rayon]
+    /// thread pool like by [`Locations::lookup_many`]. The results are in the
+    /// order of `addrs`. They are owned, see [`Network::to_owned`], so they
+    /// don't borrow from the threads' lookups.
+    ///
+    /// `Locations` is `Send` and `Sync`, so it can also be shared across
+    /// threads directly for read-only lookups.
     ///
     /// ```
     /// use libloc::Locations;
     ///
+    /// fn assert_send_sync<T: Send + Sync>() {}
+    /// assert_send_sync::<Locations>();
+    ///
     /// let locations = Locations::open("example-location.db")?;
-    /// assert_eq!(locations.country("DE").unwrap().name(), "Germany");
-    /// assert!(matches!(locations.country("XX"), None));
+    /// let addrs: Vec<_> = (0..10_000u128)
+    ///     .map(|i| std::net::Ipv6Addr::from(0x2a07_1c44_5800_0000_0000_0000_0000_0000 + i * 0x7777_7777_7777_7777_7777).into())
+    ///     .collect();
+    /// let networks = locations.par_lookup(&addrs);
+    /// assert_eq!(networks.len(), addrs.len());
+    /// for (addr, network) in addrs.iter().zip(&networks) {
+    ///     assert_eq!(network.as_ref().map(|n| n.asn()), locations.lookup(*addr).map(|n| n.asn()));
+    /// }
+    /// assert!(networks.iter().any(|n| n.is_some()));
+    /// assert!(networks.iter().any(|n| n.is_none()));
     ///
     /// # Ok::<(), libloc::OpenError>(())
     /// ```
-    pub fn country(&self, code: &str) -> Option<Country<'_>> {
-        let inner = self.inner.get();
+    ///
+    /// [rayon]: https://docs.rs/rayon
+    #[cfg(feature = "rayon")]
+    pub fn par_lookup(&self, addrs: &[IpAddr]) -> Vec<Option<NetworkOwned>> {
+        use rayon::prelude::*;
 
-        if code.len() != 2 {
-            return None;
+        // Large enough for the chunks to benefit from sorting, small enough
+        // to spread the work over the threads.
+        const CHUNK_SIZE: usize = 4096;
+        addrs
+            .par_chunks(CHUNK_SIZE)
+            .flat_map_iter(|chunk| {
+                self.lookup_many(chunk)
+                    .into_iter()
+                    .map(|network| network.map(|n| n.to_owned()))
+            })
+            .collect()
+    }
+}
+
+/// Whether two databases classify an address identically.
+///
+/// The ASN, country code and flags of the networks found by
+/// [`Locations::lookup`] are compared, the prefixes of the networks are not.
+/// If neither database knows the address, they agree as well.
+///
+/// This can be used to spot-check a database update against a sample of
+/// addresses.
+///
+/// ```
+/// use libloc::Locations;
+///
+/// let a = Locations::open("example-location.db")?;
+/// let b = Locations::open("example-location.db")?;
+/// assert!(libloc::agree(&a, &b, "2a07:1c44:5800::1".parse().unwrap()));
+/// assert!(libloc::agree(&a, &b, "127.0.0.1".parse().unwrap()));
+///
+/// # Ok::<(), libloc::OpenError>(())
+/// ```
+pub fn agree(a: &Locations, b: &Locations, addr: IpAddr) -> bool {
+    match (a.lookup(addr), b.lookup(addr)) {
+        (None, None) => true,
+        (Some(a), Some(b)) => {
+            a.inner.asn == b.inner.asn
+                && a.inner.country_code == b.inner.country_code
+                && a.inner.flags == b.inner.flags
         }
-        let code = code.as_bytes();
-        let code = [code[0], code[1]];
-        // The countries are stored sorted by country code in the database, so
-        // we can use a binary search to find a particular one.
-        let index = inner
-            .countries
-            .binary_search_by_key(&code, |c| c.code)
-            .ok()?;
-        Some(Country::from(
-            inner,
-            inner.country(index.try_into().unwrap()),
-        ))
+        _ => false,
+    }
+}
+
+/// Normalize a prefix the way the database reports its networks.
+///
+/// Host bits are cleared and IPv4-mapped IPv6 prefixes of at least 96 bits,
+/// i.e. within `::ffff:0:0/96` where the database stores IPv4 networks, are
+/// converted to IPv4 prefixes. The result can be compared directly with the
+/// prefixes returned by [`Locations::networks`].
+///
+/// ```
+/// let canonical = |net: &str| libloc::canonicalize_prefix(net.parse().unwrap()).to_string();
+/// assert_eq!(canonical("192.0.2.1/24"), "192.0.2.0/24");
+/// assert_eq!(canonical("::ffff:192.0.2.0/120"), "192.0.2.0/24");
+/// assert_eq!(canonical("::ffff:0:0/96"), "0.0.0.0/0");
+/// assert_eq!(canonical("::/0"), "::/0");
+/// assert_eq!(canonical("2001:db8::1/32"), "2001:db8::/32");
+/// ```
+pub fn canonicalize_prefix(net: IpNet) -> IpNet {
+    match net.trunc() {
+        IpNet::V4(net) => net.into(),
+        IpNet::V6(net) => unmap_prefix(net),
+    }
+}
+
+/// Stateful lookup of many addresses, e.g. from a packet stream.
+///
+/// Returned by the [`Locations::classifier`] function.
+///
+/// It remembers the position in the network tree of the last looked up `/24`
+/// (IPv4) or `/48` (IPv6) network. Consecutive lookups of addresses from the
+/// same network skip the shared part of the tree walk, which makes this
+/// faster than [`Locations::lookup`] for bursty traffic.
+pub struct Classifier<'a> {
+    inner: &'a LocationsInner<'a>,
+    last_v4: Option<(u32, Result<Cursor, Cursor>)>,
+    last_v6: Option<(u64, Result<Cursor, Cursor>)>,
+}
+
+impl<'a> Classifier<'a> {
+    const V4_CACHED_BITS: u32 = 24;
+    const V6_CACHED_BITS: u32 = 48;
+
+    /// Look up network information for an IP address.
+    ///
+    /// Returns the same result as [`Locations::lookup`].
+    pub fn classify(&mut self, addr: IpAddr) -> Option<Network<'a>> {
+        match addr {
+            IpAddr::V4(addr) => self.classify_v4(addr).map(Into::into),
+            IpAddr::V6(addr) => self.classify_v6(addr).map(Into::into),
+        }
+    }
+    fn classify_v4(&mut self, addr: Ipv4Addr) -> Option<NetworkV4<'a>> {
+        let inner = self.inner;
+        let bits = u32::from(addr);
+        let prefix = bits >> (32 - Self::V4_CACHED_BITS);
+        let cursor = match self.last_v4 {
+            Some((last, cursor)) if last == prefix => cursor,
+            _ => {
+                let cursor = inner.walk(
+                    Cursor::root(inner.ipv4_network_node?),
+                    bits.reverse_bits().into(),
+                    Self::V4_CACHED_BITS,
+                );
+                self.last_v4 = Some((prefix, cursor));
+                cursor
+            }
+        };
+        let cursor = cursor.and_then(|c| {
+            inner.walk(
+                c,
+                (bits.reverse_bits() >> Self::V4_CACHED_BITS).into(),
+                32 - Self::V4_CACHED_BITS,
+            )
+        });
+        let network = cursor.unwrap_or_else(|c| c).network(inner)?;
+        Some(inner.network_v4(addr, network))
+    }
+    fn classify_v6(&mut self, addr: Ipv6Addr) -> Option<NetworkV6<'a>> {
+        let inner = self.inner;
+        let bits = u128::from(addr);
+        let prefix = (bits >> (128 - Self::V6_CACHED_BITS)) as u64;
+        let cursor = match self.last_v6 {
+            Some((last, cursor)) if last == prefix => cursor,
+            _ => {
+                let cursor = inner.walk(Cursor::root(0), bits.reverse_bits(), Self::V6_CACHED_BITS);
+                self.last_v6 = Some((prefix, cursor));
+                cursor
+            }
+        };
+        let cursor = cursor.and_then(|c| {
+            inner.walk(
+                c,
+                bits.reverse_bits() >> Self::V6_CACHED_BITS,
+                128 - Self::V6_CACHED_BITS,
+            )
+        });
+        let network = cursor.unwrap_or_else(|c| c).network(inner)?;
+        Some(inner.network_v6(addr, network))
     }
 }
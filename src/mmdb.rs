@@ -0,0 +1,238 @@
+use crate::LocationsInner;
+use std::collections::HashMap;
+use std::io;
+use std::io::Write;
+
+const METADATA_MARKER: &[u8] = b"\xab\xcd\xefMaxMind.com";
+/// The size of the zeroed separator between the search tree and the data
+/// section.
+const DATA_SEPARATOR: usize = 16;
+
+const TYPE_STRING: u8 = 2;
+const TYPE_UINT16: u8 = 5;
+const TYPE_UINT32: u8 = 6;
+const TYPE_MAP: u8 = 7;
+const TYPE_UINT64: u8 = 9;
+const TYPE_ARRAY: u8 = 11;
+
+/// A record of a search tree node.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Record {
+    Empty,
+    Node(u32),
+    /// Index of a libloc network.
+    Data(u32),
+}
+
+fn data_record(network: Option<u32>) -> Record {
+    network.map_or(Record::Empty, Record::Data)
+}
+
+/// Convert the libloc subtree at `index` into search tree nodes.
+///
+/// `inherited` is the most specific network above the node. MMDB only
+/// stores data in leaves, so networks of inner nodes are pushed down to
+/// their missing children.
+fn convert(
+    inner: &LocationsInner,
+    index: u32,
+    inherited: Option<u32>,
+    nodes: &mut Vec<[Record; 2]>,
+) -> Record {
+    let node = inner.network_node(index);
+    let network = node.network().or(inherited);
+    let children = [node.children[0].get(), node.children[1].get()];
+    if children == [0, 0] {
+        return data_record(network);
+    }
+    let id = nodes.len();
+    nodes.push([Record::Empty; 2]);
+    for (bit, &child) in children.iter().enumerate() {
+        nodes[id][bit] = match child {
+            0 => data_record(network),
+            child => convert(inner, child, network, nodes),
+        };
+    }
+    Record::Node(id.try_into().expect("too many nodes"))
+}
+
+/// Find the record reached by the first `len` bits of `bits`, splitting
+/// leaves on the way so that it exists.
+fn record_at(nodes: &mut Vec<[Record; 2]>, bits: u128, len: u8) -> (usize, usize) {
+    let mut cur = 0;
+    for i in 0..len - 1 {
+        let bit = (bits >> (127 - i) & 1) as usize;
+        cur = match nodes[cur][bit] {
+            Record::Node(next) => next as usize,
+            record => {
+                let next = nodes.len();
+                nodes.push([record; 2]);
+                nodes[cur][bit] = Record::Node(next.try_into().expect("too many nodes"));
+                next
+            }
+        };
+    }
+    (cur, (bits >> (128 - u32::from(len)) & 1) as usize)
+}
+
+fn control(out: &mut Vec<u8>, type_: u8, size: usize) {
+    let (size_bits, extra): (u8, &[u8]) = match size {
+        0..=28 => (size as u8, &[]),
+        29..=284 => (29, &[(size - 29) as u8]),
+        285..=65820 => (30, &((size - 285) as u16).to_be_bytes()),
+        _ => {
+            assert!(size - 65821 < 1 << 24, "MMDB value too large");
+            (31, &((size - 65821) as u32).to_be_bytes()[1..])
+        }
+    };
+    if type_ <= 7 {
+        out.push(type_ << 5 | size_bits);
+    } else {
+        out.push(size_bits);
+        out.push(type_ - 7);
+    }
+    out.extend_from_slice(extra);
+}
+
+fn string(out: &mut Vec<u8>, s: &str) {
+    control(out, TYPE_STRING, s.len());
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn uint(out: &mut Vec<u8>, type_: u8, value: u64) {
+    let bytes = value.to_be_bytes();
+    let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+    control(out, type_, bytes.len() - start);
+    out.extend_from_slice(&bytes[start..]);
+}
+
+/// Append the data of the network at `index` to `out`.
+fn network_data(inner: &LocationsInner, index: u32, out: &mut Vec<u8>) {
+    let network = inner.network(index);
+    let country_code = match network.country_code {
+        [b'X', b'X'] => None,
+        ref code => Some(String::from_utf8_lossy(code)),
+    };
+    let asn = Some(network.asn.get()).filter(|&asn| asn != 0);
+    let as_name = asn
+        .and_then(|asn| inner.find_as(asn))
+        .map(|as_| inner.string_lossy(inner.as_(as_).name));
+
+    let len = country_code.is_some() as usize + asn.is_some() as usize + as_name.is_some() as usize;
+    control(out, TYPE_MAP, len);
+    if let Some(code) = country_code {
+        string(out, "country");
+        control(out, TYPE_MAP, 1);
+        string(out, "iso_code");
+        string(out, &code);
+    }
+    if let Some(asn) = asn {
+        string(out, "autonomous_system_number");
+        uint(out, TYPE_UINT32, asn.into());
+    }
+    if let Some(name) = as_name {
+        string(out, "autonomous_system_organization");
+        string(out, &name);
+    }
+}
+
+/// Serialize the database as a [MaxMind DB].
+///
+/// [MaxMind DB]: https://maxmind.github.io/MaxMind-DB/
+pub(crate) fn write<W: Write>(inner: &LocationsInner, w: W) -> io::Result<()> {
+    let mut nodes = vec![[Record::Empty; 2]];
+    if !inner.network_nodes.is_empty() {
+        nodes.clear();
+        if let record @ (Record::Empty | Record::Data(_)) = convert(inner, 0, None, &mut nodes) {
+            // The root must be a node.
+            nodes.push([record; 2]);
+        }
+    }
+    // Readers look up IPv4 addresses under `::/96`, libloc stores them under
+    // `::ffff:0:0/96`. Make the former point at the latter.
+    let (node, bit) = record_at(&mut nodes, 0xffff << 32, 96);
+    let ipv4 = nodes[node][bit];
+    let (node, bit) = record_at(&mut nodes, 0, 96);
+    nodes[node][bit] = ipv4;
+
+    // Networks with the same country and AS share their data.
+    let mut data = Vec::new();
+    let mut offsets: HashMap<([u8; 2], u32), u32> = HashMap::new();
+    let mut network_offsets: HashMap<u32, u32> = HashMap::new();
+    for record in nodes.iter().flatten() {
+        if let Record::Data(index) = *record {
+            let network = inner.network(index);
+            let key = (network.country_code, network.asn.get());
+            let offset = *offsets.entry(key).or_insert_with(|| {
+                let offset = data.len().try_into().expect("MMDB data section too large");
+                network_data(inner, index, &mut data);
+                offset
+            });
+            network_offsets.insert(index, offset);
+        }
+    }
+
+    let node_count: u32 = nodes.len().try_into().expect("too many nodes");
+    let max_value = u64::from(node_count) + (DATA_SEPARATOR + data.len()) as u64;
+    let record_size: u16 = match max_value {
+        0..=0xff_ffff => 24,
+        0x100_0000..=0xfff_ffff => 28,
+        0x1000_0000..=0xffff_ffff => 32,
+        _ => panic!("MMDB data section too large"),
+    };
+    let value = |record: Record| match record {
+        Record::Empty => node_count,
+        Record::Node(node) => node,
+        Record::Data(index) => node_count + DATA_SEPARATOR as u32 + network_offsets[&index],
+    };
+    let mut tree = Vec::with_capacity(nodes.len() * usize::from(record_size) / 4);
+    for &[left, right] in &nodes {
+        let (left, right) = (value(left).to_be_bytes(), value(right).to_be_bytes());
+        match record_size {
+            24 => {
+                tree.extend_from_slice(&left[1..]);
+                tree.extend_from_slice(&right[1..]);
+            }
+            28 => {
+                tree.extend_from_slice(&left[1..]);
+                tree.push(left[0] << 4 | right[0]);
+                tree.extend_from_slice(&right[1..]);
+            }
+            _ => {
+                tree.extend_from_slice(&left);
+                tree.extend_from_slice(&right);
+            }
+        }
+    }
+
+    let mut metadata = Vec::new();
+    control(&mut metadata, TYPE_MAP, 9);
+    string(&mut metadata, "binary_format_major_version");
+    uint(&mut metadata, TYPE_UINT16, 2);
+    string(&mut metadata, "binary_format_minor_version");
+    uint(&mut metadata, TYPE_UINT16, 0);
+    string(&mut metadata, "build_epoch");
+    uint(&mut metadata, TYPE_UINT64, inner.header.created_at.get());
+    string(&mut metadata, "database_type");
+    string(&mut metadata, "libloc");
+    string(&mut metadata, "description");
+    control(&mut metadata, TYPE_MAP, 1);
+    string(&mut metadata, "en");
+    string(&mut metadata, &inner.string_lossy(inner.header.description));
+    string(&mut metadata, "ip_version");
+    uint(&mut metadata, TYPE_UINT16, 6);
+    string(&mut metadata, "languages");
+    control(&mut metadata, TYPE_ARRAY, 0);
+    string(&mut metadata, "node_count");
+    uint(&mut metadata, TYPE_UINT32, node_count.into());
+    string(&mut metadata, "record_size");
+    uint(&mut metadata, TYPE_UINT16, record_size.into());
+
+    let mut w = io::BufWriter::new(w);
+    w.write_all(&tree)?;
+    w.write_all(&[0; DATA_SEPARATOR])?;
+    w.write_all(&data)?;
+    w.write_all(METADATA_MARKER)?;
+    w.write_all(&metadata)?;
+    w.flush()
+}
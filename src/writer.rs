@@ -0,0 +1,404 @@
+use crate::format;
+use crate::Locations;
+use crate::NetworkFlags;
+use ipnet::IpNet;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::io;
+use std::io::Write;
+use std::mem;
+use zerocopy::byteorder::big_endian as be;
+use zerocopy::AsBytes;
+use zerocopy::FromZeroes;
+
+/// Serializes a database in libloc format.
+#[derive(Default)]
+pub(crate) struct Writer {
+    pub created_at: u64,
+    pub vendor: String,
+    pub description: String,
+    pub license: String,
+    ases: BTreeMap<u32, String>,
+    countries: BTreeMap<[u8; 2], ([u8; 2], String)>,
+    /// Networks keyed by their IPv6 (or IPv4-mapped) prefix bits and length.
+    networks: BTreeMap<(u128, u8), ([u8; 2], u32, u16)>,
+}
+
+fn country_code(code: &str) -> [u8; 2] {
+    match *code.as_bytes() {
+        [a, b] => [a, b],
+        _ => panic!("country code must be two bytes: {:?}", code),
+    }
+}
+
+struct StringPool {
+    bytes: Vec<u8>,
+    offsets: HashMap<String, u32>,
+}
+
+impl StringPool {
+    fn new() -> StringPool {
+        // Offset 0 is the empty string.
+        StringPool {
+            bytes: vec![0],
+            offsets: HashMap::new(),
+        }
+    }
+    fn add(&mut self, s: &str) -> format::StrRef {
+        if s.is_empty() {
+            return format::StrRef {
+                offset: be::U32::new(0),
+            };
+        }
+        let bytes = &mut self.bytes;
+        let offset = *self.offsets.entry(s.to_owned()).or_insert_with(|| {
+            let offset = bytes.len().try_into().expect("string pool too large");
+            bytes.extend_from_slice(s.as_bytes());
+            bytes.push(0);
+            offset
+        });
+        format::StrRef {
+            offset: be::U32::new(offset),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Node {
+    children: [u32; 2],
+    network: Option<([u8; 2], u32, u16)>,
+}
+
+impl Writer {
+    pub fn add_as(&mut self, asn: u32, name: &str) {
+        self.ases.insert(asn, name.to_owned());
+    }
+    pub fn add_country(&mut self, code: &str, continent_code: &str, name: &str) {
+        self.countries.insert(
+            country_code(code),
+            (country_code(continent_code), name.to_owned()),
+        );
+    }
+    pub fn add_network(&mut self, net: IpNet, asn: u32, country_code_: &str, flags: u16) {
+        let net = net.trunc();
+        let key = match net {
+            IpNet::V4(net) => (
+                u128::from(net.network().to_ipv6_mapped()),
+                net.prefix_len() + 96,
+            ),
+            IpNet::V6(net) => (u128::from(net.network()), net.prefix_len()),
+        };
+        self.networks
+            .insert(key, (country_code(country_code_), asn, flags));
+    }
+    fn nodes(&self) -> Vec<Node> {
+        // Children are always created after their parents, so the nodes end
+        // up in the topological order libloc expects.
+        let mut nodes = vec![Node::default()];
+        for (&(bits, len), &network) in &self.networks {
+            let mut cur = 0;
+            for i in 0..len {
+                let bit = (bits >> (127 - i) & 1) as usize;
+                if nodes[cur].children[bit] == 0 {
+                    nodes[cur].children[bit] = nodes.len().try_into().expect("too many nodes");
+                    nodes.push(Node::default());
+                }
+                cur = nodes[cur].children[bit] as usize;
+            }
+            nodes[cur].network = Some(network);
+        }
+        nodes
+    }
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut pool = StringPool::new();
+        let mut header = format::Header::new_zeroed();
+        header.magic = format::MAGIC;
+        header.version = format::VERSION;
+        header.created_at = be::U64::new(self.created_at);
+        header.vendor = pool.add(&self.vendor);
+        header.description = pool.add(&self.description);
+        header.license = pool.add(&self.license);
+
+        let ases: Vec<format::As> = self
+            .ases
+            .iter()
+            .map(|(&asn, name)| format::As {
+                id: be::U32::new(asn),
+                name: pool.add(name),
+            })
+            .collect();
+        let countries: Vec<format::Country> = self
+            .countries
+            .iter()
+            .map(|(&code, (continent_code, name))| format::Country {
+                code,
+                continent_code: *continent_code,
+                name: pool.add(name),
+            })
+            .collect();
+        let mut networks = Vec::new();
+        let network_nodes: Vec<format::NetworkNode> = self
+            .nodes()
+            .into_iter()
+            .map(|node| {
+                let network = match node.network {
+                    Some((country_code, asn, flags)) => {
+                        networks.push(format::Network {
+                            country_code,
+                            _padding1: [0; 2],
+                            asn: be::U32::new(asn),
+                            flags: be::U16::new(flags),
+                            _padding2: [0; 2],
+                        });
+                        networks.len() as u32 - 1
+                    }
+                    None => u32::MAX,
+                };
+                format::NetworkNode {
+                    children: node.children.map(be::U32::new),
+                    network: be::U32::new(network),
+                }
+            })
+            .collect();
+
+        let mut bytes = vec![0; mem::size_of::<format::Header>()];
+        let section = |bytes: &mut Vec<u8>, data: &[u8]| {
+            let range = format::FileRange {
+                offset: be::U32::new(bytes.len().try_into().expect("database too large")),
+                length: be::U32::new(data.len().try_into().expect("database too large")),
+            };
+            bytes.extend_from_slice(data);
+            range
+        };
+        header.as_ = section(&mut bytes, ases.as_bytes());
+        header.networks = section(&mut bytes, networks.as_bytes());
+        header.network_nodes = section(&mut bytes, network_nodes.as_bytes());
+        header.countries = section(&mut bytes, countries.as_bytes());
+        header.string_pool = section(&mut bytes, &pool.bytes);
+        bytes[..mem::size_of::<format::Header>()].copy_from_slice(header.as_bytes());
+        bytes
+    }
+}
+
+/// Build a database in libloc format.
+///
+/// Add ASes, countries and networks, then serialize the database with
+/// [`DatabaseBuilder::write_to`] or [`DatabaseBuilder::to_bytes`]. The
+/// output can be read with [`Locations::open`] or [`Locations::from_bytes`].
+///
+/// Networks are stored in a radix tree, strings are deduplicated in the
+/// string pool. Adding the same AS, country or network twice replaces the
+/// earlier entry.
+///
+/// The database is not signed.
+///
+/// ```
+/// use libloc::{DatabaseBuilder, Locations, NetworkFlags};
+/// use std::fs::File;
+///
+/// let mut builder = DatabaseBuilder::new();
+/// builder
+///     .set_created_at(1707258629)
+///     .set_vendor("Example")
+///     .add_as(13335, "Cloudflare")
+///     .add_country("AU", "OC", "Australia")
+///     .add_network("1.0.0.0/24".parse().unwrap(), 13335, "AU", NetworkFlags::ANYCAST);
+///
+/// let path = std::env::temp_dir().join("libloc-database-builder-doctest.db");
+/// builder.write_to(File::create(&path).unwrap()).unwrap();
+///
+/// let locations = Locations::open(&path)?;
+/// # std::fs::remove_file(&path).unwrap();
+/// assert_eq!(locations.created_at_system_time(), std::time::UNIX_EPOCH + std::time::Duration::from_secs(1707258629));
+/// assert_eq!(locations.vendor(), "Example");
+/// let network = locations.lookup("1.0.0.1".parse().unwrap()).unwrap();
+/// assert_eq!(network.addrs().to_string(), "1.0.0.0/24");
+/// assert_eq!(network.asn(), 13335);
+/// assert_eq!(network.country_code(), "AU");
+/// assert!(network.is_anycast());
+/// assert_eq!(locations.as_(13335).unwrap().name(), "Cloudflare");
+/// assert_eq!(locations.country("AU").unwrap().name(), "Australia");
+/// # Ok::<(), libloc::OpenError>(())
+/// ```
+#[derive(Default)]
+pub struct DatabaseBuilder {
+    writer: Writer,
+}
+
+impl DatabaseBuilder {
+    /// Create a builder for an empty database.
+    pub fn new() -> DatabaseBuilder {
+        DatabaseBuilder::default()
+    }
+    /// Set the creation time of the database, in seconds since the Unix
+    /// epoch.
+    ///
+    /// Defaults to 0.
+    pub fn set_created_at(&mut self, created_at: u64) -> &mut DatabaseBuilder {
+        self.writer.created_at = created_at;
+        self
+    }
+    /// Set the vendor of the database.
+    pub fn set_vendor(&mut self, vendor: &str) -> &mut DatabaseBuilder {
+        self.writer.vendor = vendor.to_owned();
+        self
+    }
+    /// Set the description of the database.
+    pub fn set_description(&mut self, description: &str) -> &mut DatabaseBuilder {
+        self.writer.description = description.to_owned();
+        self
+    }
+    /// Set the license of the database.
+    pub fn set_license(&mut self, license: &str) -> &mut DatabaseBuilder {
+        self.writer.license = license.to_owned();
+        self
+    }
+    /// Add an AS with the given ASN and name.
+    pub fn add_as(&mut self, asn: u32, name: &str) -> &mut DatabaseBuilder {
+        self.writer.add_as(asn, name);
+        self
+    }
+    /// Add a country with the given code, continent code and name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `code` or `continent_code` is not two bytes long.
+    pub fn add_country(
+        &mut self,
+        code: &str,
+        continent_code: &str,
+        name: &str,
+    ) -> &mut DatabaseBuilder {
+        self.writer.add_country(code, continent_code, name);
+        self
+    }
+    /// Add a network with the given ASN, country code and flags.
+    ///
+    /// Host bits of `net` are ignored. IPv4 networks are stored as
+    /// IPv4-mapped IPv6 networks, like libloc does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `country_code` is not two bytes long.
+    pub fn add_network(
+        &mut self,
+        net: IpNet,
+        asn: u32,
+        country_code: &str,
+        flags: NetworkFlags,
+    ) -> &mut DatabaseBuilder {
+        self.writer
+            .add_network(net, asn, country_code, flags.bits());
+        self
+    }
+    /// Serialize the database.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the database doesn't fit the 32-bit offsets of the format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.writer.to_bytes()
+    }
+    /// Serialize the database and write it to `w`.
+    ///
+    /// # Panics
+    ///
+    /// See [`DatabaseBuilder::to_bytes`].
+    pub fn write_to<W: Write>(&self, mut w: W) -> io::Result<()> {
+        w.write_all(&self.to_bytes())?;
+        w.flush()
+    }
+    /// Build the database in memory.
+    ///
+    /// This is a shortcut for passing [`DatabaseBuilder::to_bytes`] to
+    /// [`Locations::from_bytes`].
+    pub fn build(&self) -> Locations {
+        Locations::from_bytes(self.to_bytes()).expect("built database is valid")
+    }
+}
+
+/// Build small databases in memory, for tests.
+///
+/// This makes it possible to test code using this crate against tiny
+/// synthetic databases instead of shipping a database file. It's a more
+/// concise front end to [`DatabaseBuilder`], taking networks in CIDR
+/// notation.
+///
+/// ```
+/// use libloc::TestDatabaseBuilder;
+///
+/// let locations = TestDatabaseBuilder::new()
+///     .add_network("1.0.0.0/24", 13335, "AU")
+///     .add_as(13335, "Cloudflare")
+///     .add_country("AU", "OC", "Australia")
+///     .build();
+///
+/// let network = locations.lookup("1.0.0.1".parse().unwrap()).unwrap();
+/// assert_eq!(network.addrs().to_string(), "1.0.0.0/24");
+/// assert_eq!(network.asn(), 13335);
+/// assert_eq!(network.country_code(), "AU");
+/// assert_eq!(locations.as_(13335).unwrap().name(), "Cloudflare");
+/// assert_eq!(locations.country("AU").unwrap().name(), "Australia");
+/// assert!(locations.lookup("1.0.1.1".parse().unwrap()).is_none());
+/// ```
+#[derive(Default)]
+pub struct TestDatabaseBuilder {
+    builder: DatabaseBuilder,
+}
+
+impl TestDatabaseBuilder {
+    /// Create a builder for an empty database.
+    pub fn new() -> TestDatabaseBuilder {
+        TestDatabaseBuilder::default()
+    }
+    /// Add a network with the given ASN and country code.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `net` is not a valid prefix in CIDR notation or if
+    /// `country_code` is not two bytes long.
+    pub fn add_network(self, net: &str, asn: u32, country_code: &str) -> TestDatabaseBuilder {
+        self.add_network_with_flags(net, asn, country_code, NetworkFlags::empty())
+    }
+    /// Add a network with the given ASN, country code and flags.
+    ///
+    /// # Panics
+    ///
+    /// See [`TestDatabaseBuilder::add_network`].
+    pub fn add_network_with_flags(
+        mut self,
+        net: &str,
+        asn: u32,
+        country_code: &str,
+        flags: NetworkFlags,
+    ) -> TestDatabaseBuilder {
+        let net = net
+            .parse()
+            .unwrap_or_else(|e| panic!("invalid network {:?}: {}", net, e));
+        self.builder.add_network(net, asn, country_code, flags);
+        self
+    }
+    /// Add an AS with the given ASN and name.
+    pub fn add_as(mut self, asn: u32, name: &str) -> TestDatabaseBuilder {
+        self.builder.add_as(asn, name);
+        self
+    }
+    /// Add a country with the given code, continent code and name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `code` or `continent_code` is not two bytes long.
+    pub fn add_country(
+        mut self,
+        code: &str,
+        continent_code: &str,
+        name: &str,
+    ) -> TestDatabaseBuilder {
+        self.builder.add_country(code, continent_code, name);
+        self
+    }
+    /// Build the database.
+    pub fn build(self) -> Locations {
+        self.builder.build()
+    }
+}
@@ -1,5 +1,8 @@
 use clap::Parser;
+use clap::ValueEnum;
 use libloc::Locations;
+use std::io;
+use std::io::BufRead;
 use std::net::IpAddr;
 use std::path::PathBuf;
 
@@ -11,41 +14,124 @@ struct Args {
     /// about the database instead.
     ip_addrs: Vec<IpAddr>,
 
+    /// Also read IP addresses from stdin, one per line. Blank lines are
+    /// skipped, invalid ones reported on stderr.
+    #[arg(long)]
+    stdin: bool,
+
+    /// Output format of lookups and meta information.
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Show the database header and section layout instead. This works even
+    /// if the data sections are corrupt.
+    #[arg(long)]
+    header: bool,
+
     /// Path to database.
     #[arg(long, default_value = "/usr/share/libloc-location/location.db")]
     database: PathBuf,
 }
 
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Format {
+    /// Human-readable lines.
+    Text,
+    /// One JSON object per line.
+    Json,
+}
+
+fn print_lookup(locations: &Locations, addr: IpAddr, format: Format) {
+    if let Format::Json = format {
+        let mut result = match locations.describe(addr) {
+            Some(description) => serde_json::to_value(description).unwrap(),
+            None => serde_json::json!({ "network": null, "as": null, "country": null }),
+        };
+        result["address"] = serde_json::json!(addr);
+        println!("{}", result);
+        return;
+    }
+    match locations.describe(addr) {
+        Some(description) => {
+            let network = description.network();
+            let as_name = description
+                .as_()
+                .map(|as_| as_.name())
+                .unwrap_or("AS name unknown");
+            let country = match description.country() {
+                Some(country) => format!(
+                    "{}:{}, {}",
+                    country.continent_code(),
+                    country.code(),
+                    country.name()
+                ),
+                None => format!("{}, country unknown", network.country_code()),
+            };
+            println!(
+                "{} ({}): AS{}, {}, {}",
+                addr,
+                network.addrs(),
+                network.asn(),
+                as_name,
+                country
+            );
+        }
+        None => println!("{}: unknown", addr),
+    }
+}
+
 fn main() {
     let args = Args::parse();
 
+    if args.header {
+        let header = Locations::header_info(&args.database).unwrap();
+        fn string(s: &Option<String>) -> &str {
+            s.as_deref().unwrap_or("<corrupt string pool>")
+        }
+        println!("version: {}", header.version);
+        println!("created_at: {}", header.created_at);
+        println!("vendor: {}", string(&header.vendor));
+        println!("description: {}", string(&header.description));
+        println!("license: {}", string(&header.license));
+        println!("file size: {}", header.file_size);
+        println!("signed: {}", if header.is_signed() { "yes" } else { "no" });
+        println!();
+        println!("{:<16}{:>12}{:>12}", "section", "offset", "length");
+        for section in &header.sections {
+            println!(
+                "{:<16}{:>12}{:>12}",
+                section.name, section.offset, section.length
+            );
+        }
+        return;
+    }
+
     let locations = Locations::open(&args.database).unwrap();
-    if args.ip_addrs.is_empty() {
+    if args.ip_addrs.is_empty() && !args.stdin {
+        if let Format::Json = args.format {
+            println!("{}", serde_json::to_string(&locations.metadata()).unwrap());
+            return;
+        }
         println!("created_at: {}", locations.created_at());
         println!("\nvendor:\n{}", locations.vendor());
         println!("\ndescription:\n{}", locations.description());
         println!("\nlicense:\n{}", locations.license());
     } else {
         for addr in args.ip_addrs {
-            match locations.lookup(addr) {
-                Some(network) => {
-                    let as_name = locations
-                        .as_(network.asn())
-                        .map(|as_| as_.name())
-                        .unwrap_or("AS name unknown");
-                    let country = locations.country(network.country_code()).expect("country");
-                    println!(
-                        "{} ({}): AS{}, {}, {}:{}, {}",
-                        addr,
-                        network.addrs(),
-                        network.asn(),
-                        as_name,
-                        country.continent_code(),
-                        country.code(),
-                        country.name()
-                    );
+            print_lookup(&locations, addr, args.format);
+        }
+        if args.stdin {
+            for line in io::stdin().lock().split(b'\n') {
+                let line = line.unwrap();
+                let line = String::from_utf8_lossy(&line);
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+                match line.parse() {
+                    Ok(addr) => print_lookup(&locations, addr, args.format),
+                    Err(e) => eprintln!("{}: {}", line, e),
                 }
-                None => println!("{}: unknown", addr),
             }
         }
     }